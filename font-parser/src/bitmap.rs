@@ -0,0 +1,320 @@
+use std::ops::Range;
+
+use crate::font_reader::{FontReader, Tag};
+use crate::font_table_parser::{FontData, FontError, FONT_SIZE_CONSTANT, GLYF};
+
+pub const SBIX: Tag = Tag::new(b"sbix");
+pub const CBLC: Tag = Tag::new(b"CBLC");
+pub const CBDT: Tag = Tag::new(b"CBDT");
+pub const EBLC: Tag = Tag::new(b"EBLC");
+pub const EBDT: Tag = Tag::new(b"EBDT");
+
+const PNG: Tag = Tag::new(b"png ");
+const DUPE: Tag = Tag::new(b"dupe"); // sbix glyphs that reuse another glyph's bitmap
+const MAX_DUPES: usize = 4;
+
+// the pixels of an embedded bitmap, still encoded the way the font stores them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BitmapImage<'a> {
+    Png(&'a [u8]), // sbix and CBDT color glyphs, a whole png file
+    // EBDT strikes, rows of bit_depth bit pixels from the top. byte aligned rows start on a fresh byte,
+    // bit aligned ones carry on right where the previous row stopped
+    Gray { data: &'a [u8], bit_depth: u8, byte_aligned: bool },
+}
+
+// positions are in pixels of the strike, see pixel_size for the outline units
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BitmapGlyph<'a> {
+    pub ppem: u16, // the strike it came out of
+    pub left: f32, // glyph origin to the left edge
+    pub bottom: f32, // baseline up to the bottom edge
+    pub width: u32,
+    pub height: u32,
+    pub image: BitmapImage<'a>,
+}
+
+impl BitmapGlyph<'_> {
+    // how big one pixel is, scaled like the outlines
+    pub fn pixel_size(&self) -> f32 {
+        FONT_SIZE_CONSTANT / self.ppem as f32
+    }
+
+    // one byte per pixel from the top row down, 255 is fully inked. pngs need an image decoder so they get None
+    pub fn coverage(&self) -> Option<Vec<u8>> {
+        let BitmapImage::Gray { data, bit_depth, byte_aligned } = self.image else { return None };
+        let (width, height, depth) = (self.width as usize, self.height as usize, bit_depth as usize);
+        let max = (1u32 << depth) - 1;
+        let row_bits = if byte_aligned { (width * depth).div_ceil(8) * 8 } else { width * depth };
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                // depths are 1, 2, 4 or 8 so a pixel never straddles two bytes
+                let bit = y * row_bits + x * depth;
+                let value = (*data.get(bit / 8)? as u32 >> (8 - depth - bit % 8)) & max;
+                pixels.push((value * 255 / max) as u8);
+            }
+        }
+        Some(pixels)
+    }
+}
+
+// one strike out of CBLC/EBLC
+struct BitmapSize {
+    index_array: usize,
+    num_index_tables: usize,
+    glyphs: Range<usize>,
+    ppem: u16,
+    bit_depth: u8,
+}
+
+// the horizontal half of small and big glyph metrics
+#[derive(Clone, Copy)]
+struct BitmapMetrics {
+    height: u8,
+    width: u8,
+    bearing_x: i8,
+    bearing_y: i8, // baseline up to the top edge
+}
+
+impl FontData {
+    pub fn has_bitmaps(&self) -> bool {
+        self.font_table.contains_key(&SBIX)
+            || (self.font_table.contains_key(&CBLC) && self.font_table.contains_key(&CBDT))
+            || (self.font_table.contains_key(&EBLC) && self.font_table.contains_key(&EBDT))
+    }
+
+    // color bitmaps replace the outlines like COLR does, plain EBDT strikes in an outline font are
+    // hand tuned versions for old screen sizes so those only get used by fonts without any outlines
+    pub fn prefers_bitmaps(&self) -> bool {
+        self.has_bitmaps()
+            && (self.font_table.contains_key(&SBIX) || self.font_table.contains_key(&CBDT) || !self.font_table.contains_key(&GLYF))
+    }
+
+    // the bitmap that suits ppem (pixels per em on screen) best, the smallest strike at least that big
+    // since scaling down looks better than up. strikes are allowed to leave glyphs out so the others get tried too
+    pub fn bitmap_glyph(&self, glyph_index: usize, ppem: f32) -> Result<Option<BitmapGlyph<'_>>, FontError> {
+        if glyph_index >= self.num_glyphs {
+            return Ok(None);
+        }
+        let ppem = ppem.round().clamp(1.0, u16::MAX as f32) as u16;
+
+        if self.font_table.contains_key(&SBIX) && let Some(glyph) = self.sbix_glyph(glyph_index, ppem)? {
+            return Ok(Some(glyph));
+        }
+        for (location, data) in [(CBLC, CBDT), (EBLC, EBDT)] {
+            if !self.font_table.contains_key(&location) || !self.font_table.contains_key(&data) {
+                continue;
+            }
+            if let Some(glyph) = self.strike_glyph(location, data, glyph_index, ppem)? {
+                return Ok(Some(glyph));
+            }
+        }
+        Ok(None)
+    }
+
+    // https://learn.microsoft.com/en-us/typography/opentype/spec/sbix
+    fn sbix_glyph(&self, glyph_index: usize, ppem: u16) -> Result<Option<BitmapGlyph<'_>>, FontError> {
+        let sbix = self.table(SBIX)?;
+        let mut header = sbix;
+        header.skip_bytes(4)?; // version, flags
+        let num_strikes = header.read_u32()? as usize;
+        let (mut strikes, mut ppems) = (Vec::new(), Vec::new()); // counts come straight from the font, no preallocating
+        for _ in 0..num_strikes {
+            let strike = sbix.reader_from(header.read_offset32()?.to_usize())?;
+            let mut reader = strike;
+            ppems.push(reader.read_u16()?);
+            strikes.push(strike);
+        }
+
+        for strike_index in strike_order(&ppems, ppem) {
+            let strike = strikes[strike_index];
+            let mut glyph_index = glyph_index;
+            for _ in 0..MAX_DUPES {
+                let mut offsets = strike;
+                offsets.go_to(4 + glyph_index * 4)?; // past ppem and ppi
+                let (start, end) = (offsets.read_offset32()?.to_usize(), offsets.read_offset32()?.to_usize());
+                if end <= start + 8 {
+                    break; // nothing for this glyph in this strike
+                }
+                let mut reader = strike.sub_reader(start, end - start)?;
+                let (left, bottom) = (reader.read_i16()?, reader.read_i16()?);
+                let graphic_type = reader.read_tag()?;
+                let image = reader.reader_from(8)?;
+                if graphic_type == DUPE {
+                    let mut dupe = image;
+                    glyph_index = dupe.read_u16()? as usize;
+                    continue;
+                }
+                if graphic_type != PNG {
+                    break; // jpg and tiff would need decoders of their own
+                }
+                let (width, height) = png_size(image)?;
+                return Ok(Some(BitmapGlyph {
+                    ppem: ppems[strike_index],
+                    left: left as f32,
+                    bottom: bottom as f32,
+                    width,
+                    height,
+                    image: BitmapImage::Png(image.data()),
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    // https://learn.microsoft.com/en-us/typography/opentype/spec/eblc
+    // CBLC/CBDT are the same tables with png image formats added, so one reader does both
+    fn strike_glyph(&self, location: Tag, data: Tag, glyph_index: usize, ppem: u16) -> Result<Option<BitmapGlyph<'_>>, FontError> {
+        let (location, data) = (self.table(location)?, self.table(data)?);
+        let mut header = location;
+        header.skip_bytes(4)?; // version
+        let num_sizes = header.read_u32()? as usize;
+
+        let mut sizes = Vec::new();
+        for i in 0..num_sizes {
+            let mut record = location.sub_reader(8 + i * 48, 48)?;
+            let index_array = record.read_offset32()?.to_usize();
+            record.skip_bytes(4)?; // indexTablesSize
+            let num_index_tables = record.read_u32()? as usize;
+            record.skip_bytes(28)?; // colorRef, horizontal and vertical line metrics
+            let (start_glyph, end_glyph) = (record.read_u16()? as usize, record.read_u16()? as usize);
+            record.skip_bytes(1)?; // ppemX, bitmaps are square pixels in practice
+            let ppem = record.read_byte()? as u16;
+            let bit_depth = record.read_byte()?;
+            sizes.push(BitmapSize { index_array, num_index_tables, glyphs: start_glyph..end_glyph + 1, ppem, bit_depth });
+        }
+
+        let ppems: Vec<u16> = sizes.iter().map(|size| size.ppem).collect();
+        for size_index in strike_order(&ppems, ppem) {
+            let size = &sizes[size_index];
+            if !size.glyphs.contains(&glyph_index) {
+                continue;
+            }
+            if let Some(glyph) = read_strike_glyph(location, data, size, glyph_index)? {
+                return Ok(Some(glyph));
+            }
+        }
+        Ok(None)
+    }
+}
+
+// strike indices from the best fit for ppem to the worst: the ones at least as big smallest first, then the smaller ones.
+// a 0 ppem strike has no pixel size (see pixel_size) so those are left out
+fn strike_order(ppems: &[u16], ppem: u16) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..ppems.len()).filter(|i| ppems[*i] != 0).collect();
+    order.sort_by_key(|i| (ppems[*i] < ppem, ppems[*i].abs_diff(ppem)));
+    order
+}
+
+// width and height out of the IHDR chunk, which always comes right after the signature
+fn png_size(mut png: FontReader) -> std::io::Result<(u32, u32)> {
+    png.go_to(16)?;
+    Ok((png.read_u32()?, png.read_u32()?))
+}
+
+fn read_strike_glyph<'a>(location: FontReader<'a>, data: FontReader<'a>, size: &BitmapSize, glyph_index: usize) -> std::io::Result<Option<BitmapGlyph<'a>>> {
+    let index_array = location.reader_from(size.index_array)?;
+    let mut records = index_array;
+    for _ in 0..size.num_index_tables {
+        let (first_glyph, last_glyph) = (records.read_u16()? as usize, records.read_u16()? as usize);
+        let offset = records.read_offset32()?.to_usize();
+        if !(first_glyph..=last_glyph).contains(&glyph_index) {
+            continue;
+        }
+
+        let mut subtable = index_array.reader_from(offset)?;
+        let (index_format, image_format) = (subtable.read_u16()?, subtable.read_u16()?);
+        let image_data_offset = subtable.read_offset32()?.to_usize();
+        let index = glyph_index - first_glyph;
+        // where the image sits after image_data_offset, formats 2 and 5 give every glyph the same size and metrics
+        let (range, shared_metrics) = match index_format {
+            1 => {
+                subtable.skip_bytes(index * 4)?;
+                (subtable.read_offset32()?.to_usize()..subtable.read_offset32()?.to_usize(), None)
+            }
+            2 => {
+                let image_size = subtable.read_u32()? as usize;
+                let metrics = read_big_metrics(&mut subtable)?;
+                (index * image_size..(index + 1) * image_size, Some(metrics))
+            }
+            3 => {
+                subtable.skip_bytes(index * 2)?;
+                (subtable.read_offset16()?.to_usize()..subtable.read_offset16()?.to_usize(), None)
+            }
+            4 => {
+                let num_glyphs = subtable.read_u32()? as usize;
+                let pairs = subtable.reader_from(subtable.get_location())?;
+                let Some(position) = (0..num_glyphs).find(|i| pairs.sub_reader(i * 4, 2).and_then(|mut id| id.read_u16()).is_ok_and(|id| id as usize == glyph_index)) else {
+                    return Ok(None);
+                };
+                let mut offsets = pairs.reader_from(position * 4 + 2)?;
+                let start = offsets.read_offset16()?.to_usize();
+                offsets.skip_bytes(2)?; // the next glyph id
+                (start..offsets.read_offset16()?.to_usize(), None)
+            }
+            5 => {
+                let image_size = subtable.read_u32()? as usize;
+                let metrics = read_big_metrics(&mut subtable)?;
+                let num_glyphs = subtable.read_u32()? as usize;
+                let ids = subtable.reader_from(subtable.get_location())?;
+                let Some(position) = (0..num_glyphs).find(|i| ids.sub_reader(i * 2, 2).and_then(|mut id| id.read_u16()).is_ok_and(|id| id as usize == glyph_index)) else {
+                    return Ok(None);
+                };
+                (position * image_size..(position + 1) * image_size, Some(metrics))
+            }
+            _ => return Ok(None),
+        };
+        if range.end <= range.start {
+            return Ok(None); // the glyph is in the range but has no bitmap, like space
+        }
+        let image = data.sub_reader(image_data_offset + range.start, range.len())?;
+        return read_image(image, image_format, size, shared_metrics);
+    }
+    Ok(None)
+}
+
+// https://learn.microsoft.com/en-us/typography/opentype/spec/ebdt
+fn read_image<'a>(mut image: FontReader<'a>, format: u16, size: &BitmapSize, shared_metrics: Option<BitmapMetrics>) -> std::io::Result<Option<BitmapGlyph<'a>>> {
+    let metrics = match format {
+        1 | 2 | 17 => read_small_metrics(&mut image)?,
+        6 | 7 | 18 => read_big_metrics(&mut image)?,
+        5 | 19 => match shared_metrics {
+            Some(metrics) => metrics,
+            None => return Ok(None),
+        },
+        _ => return Ok(None), // 8 and 9 are put together out of other bitmaps, rare enough to leave out
+    };
+
+    let rest = image.reader_from(image.get_location())?;
+    let bitmap = match format {
+        17..=19 => {
+            let mut rest = rest;
+            let length = rest.read_u32()? as usize;
+            BitmapImage::Png(rest.sub_reader(4, length)?.data())
+        }
+        _ if !matches!(size.bit_depth, 1 | 2 | 4 | 8) => return Ok(None),
+        1 | 6 => BitmapImage::Gray { data: rest.data(), bit_depth: size.bit_depth, byte_aligned: true },
+        _ => BitmapImage::Gray { data: rest.data(), bit_depth: size.bit_depth, byte_aligned: false },
+    };
+    Ok(Some(BitmapGlyph {
+        ppem: size.ppem,
+        left: metrics.bearing_x as f32,
+        bottom: metrics.bearing_y as f32 - metrics.height as f32,
+        width: metrics.width as u32,
+        height: metrics.height as u32,
+        image: bitmap,
+    }))
+}
+
+fn read_small_metrics(reader: &mut FontReader) -> std::io::Result<BitmapMetrics> {
+    let metrics = BitmapMetrics { height: reader.read_byte()?, width: reader.read_byte()?, bearing_x: reader.read_i8()?, bearing_y: reader.read_i8()? };
+    reader.skip_bytes(1)?; // advance, hmtx already has it
+    Ok(metrics)
+}
+
+fn read_big_metrics(reader: &mut FontReader) -> std::io::Result<BitmapMetrics> {
+    let metrics = read_small_metrics(reader)?;
+    reader.skip_bytes(3)?; // the vertical bearings and advance
+    Ok(metrics)
+}
@@ -0,0 +1,90 @@
+use crate::font_reader::Tag;
+use crate::font_table_parser::{FontData, FontError};
+
+pub const COLR: Tag = Tag::new(b"COLR");
+pub const CPAL: Tag = Tag::new(b"CPAL");
+
+pub(crate) const FOREGROUND: u16 = 0xFFFF; // layers with this palette index use the text color
+
+// one layer of a color glyph, drawn bottom to top
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorLayer {
+    pub glyph_index: usize, // an ordinary glyph with the layer's outline
+    pub palette_index: Option<usize>, // None is the text color
+}
+
+// srgb, straight alpha
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PaletteColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl FontData {
+    // https://learn.microsoft.com/en-us/typography/opentype/spec/colr
+    // the version 0 layers of a glyph, empty for glyphs (and fonts) that aren't colored.
+    // looked up in the table every time, the base glyph records are sorted so it's a binary search
+    pub fn color_layers(&self, glyph_index: usize) -> Result<Vec<ColorLayer>, FontError> {
+        if !self.font_table.contains_key(&COLR) {
+            return Ok(Vec::new());
+        }
+        let colr = self.table(COLR)?;
+        let mut header = colr;
+        header.skip_bytes(2)?; // version, v1 keeps the v0 records in front
+        let num_base_glyphs = header.read_u16()? as usize;
+        let base_glyphs = colr.reader_from(header.read_offset32()?.to_usize())?;
+        let layers = colr.reader_from(header.read_offset32()?.to_usize())?;
+
+        let (mut low, mut high) = (0, num_base_glyphs);
+        while low < high {
+            let middle = (low + high) / 2;
+            let mut record = base_glyphs.sub_reader(middle * 6, 6)?;
+            let base_glyph = record.read_u16()? as usize;
+            if base_glyph < glyph_index {
+                low = middle + 1;
+            } else if base_glyph > glyph_index {
+                high = middle;
+            } else {
+                let (first_layer, num_layers) = (record.read_u16()? as usize, record.read_u16()? as usize);
+                let mut reader = layers.reader_from(first_layer * 4)?;
+                let mut color_layers = Vec::with_capacity(num_layers);
+                for _ in 0..num_layers {
+                    let (glyph_index, palette_index) = (reader.read_u16()? as usize, reader.read_u16()?);
+                    color_layers.push(ColorLayer { glyph_index, palette_index: (palette_index != FOREGROUND).then_some(palette_index as usize) });
+                }
+                return Ok(color_layers);
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    // https://learn.microsoft.com/en-us/typography/opentype/spec/cpal
+    // every palette with all its colors, palette 0 is the default one
+    pub fn palettes(&self) -> Result<Vec<Vec<PaletteColor>>, FontError> {
+        if !self.font_table.contains_key(&CPAL) {
+            return Ok(Vec::new());
+        }
+        let cpal = self.table(CPAL)?;
+        let mut header = cpal;
+        header.skip_bytes(2)?; // version, v1 only adds palette types and names
+        let num_entries = header.read_u16()? as usize;
+        let num_palettes = header.read_u16()? as usize;
+        header.skip_bytes(2)?; // numColorRecords
+        let color_records = cpal.reader_from(header.read_offset32()?.to_usize())?;
+
+        let mut palettes = Vec::with_capacity(num_palettes);
+        for _ in 0..num_palettes {
+            let first_color = header.read_u16()? as usize;
+            let mut reader = color_records.reader_from(first_color * 4)?;
+            let mut colors = Vec::with_capacity(num_entries);
+            for _ in 0..num_entries {
+                let [b, g, r, a] = reader.read_bytes()?;
+                colors.push(PaletteColor { r, g, b, a });
+            }
+            palettes.push(colors);
+        }
+        Ok(palettes)
+    }
+}
@@ -0,0 +1,74 @@
+use crate::font_table_parser::{FontData, Glyph, FONT_SIZE_CONSTANT};
+use crate::lint::flatten;
+
+// where underlines, strikethroughs and overlines go, in the same units as the outlines (y up from the baseline).
+// positions are the top of the line, like post and OS/2 give them
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DecorationMetrics {
+    pub underline_position: f32,
+    pub underline_thickness: f32,
+    pub strikeout_position: f32,
+    pub strikeout_thickness: f32,
+    pub overline_position: f32, // the font's ascent, same thickness as the underline
+}
+
+impl FontData {
+    // post for the underline, OS/2 for the strikeout. fonts that leave them out (or zero) get guesses off the em size
+    pub fn decoration_metrics(&self) -> DecorationMetrics {
+        let scaled = |value: i16| value as f32 * self.font_scale;
+
+        let (underline_position, underline_thickness) = match &self.post {
+            Some(post) if post.underline_thickness > 0 => (scaled(post.underline_position), scaled(post.underline_thickness)),
+            _ => (-0.1 * FONT_SIZE_CONSTANT, 0.05 * FONT_SIZE_CONSTANT),
+        };
+        let (strikeout_position, strikeout_thickness) = match &self.os2 {
+            Some(os2) if os2.strikeout_size > 0 => (scaled(os2.strikeout_position), scaled(os2.strikeout_size)),
+            // middle of the lowercase letters
+            Some(os2) if os2.x_height.is_some_and(|x_height| x_height > 0) => {
+                (scaled(os2.x_height.unwrap_or_default()) * 0.5 + underline_thickness * 0.5, underline_thickness)
+            }
+            _ => (self.ascent * 0.3, underline_thickness),
+        };
+
+        DecorationMetrics { underline_position, underline_thickness, strikeout_position, strikeout_thickness, overline_position: self.ascent }
+    }
+}
+
+// x ranges (from the glyph origin) where the outline is inside the band between y_min and y_max, sorted and merged.
+// underlines leave these out (plus some room) so they skip over descenders instead of running through them
+pub fn ink_in_band(glyph: &Glyph, y_min: f32, y_max: f32) -> Vec<(f32, f32)> {
+    // one range per contour, from its leftmost to its rightmost point inside the band, so the whole
+    // stroke is left out and not just the two edges of it
+    let mut ranges = Vec::new();
+    for contour in glyph.contour_coordinates.iter() {
+        let polyline = flatten(contour);
+        let mut range: Option<(f32, f32)> = None;
+        for i in 0..polyline.len() {
+            let (a, b) = (polyline[i], polyline[(i + 1) % polyline.len()]);
+            if a.y.max(b.y) < y_min || a.y.min(b.y) > y_max {
+                continue;
+            }
+            // the part of the segment inside the band
+            let (t0, t1) = if (b.y - a.y).abs() < f32::EPSILON {
+                (0.0, 1.0)
+            } else {
+                let (ta, tb) = ((y_min - a.y) / (b.y - a.y), (y_max - a.y) / (b.y - a.y));
+                (ta.min(tb).max(0.0), ta.max(tb).min(1.0))
+            };
+            let (x0, x1) = (a.x + (b.x - a.x) * t0, a.x + (b.x - a.x) * t1);
+            let (start, end) = range.unwrap_or((f32::MAX, f32::MIN));
+            range = Some((start.min(x0).min(x1), end.max(x0).max(x1)));
+        }
+        ranges.extend(range);
+    }
+
+    ranges.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut merged: Vec<(f32, f32)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
@@ -0,0 +1,241 @@
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Deref;
+use std::path::Path;
+
+// https://learn.microsoft.com/en-us/typography/opentype/spec/otff#data-types
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tag(pub [u8; 4]);
+
+impl Tag {
+    pub const fn new(tag: &[u8; 4]) -> Self {
+        Tag(*tag)
+    }
+}
+
+impl Default for Tag {
+    fn default() -> Self {
+        Tag(*b"    ")
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{}", *byte as char))
+    }
+}
+
+impl fmt::Debug for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Tag({self})")
+    }
+}
+
+// 16.16 fixed point
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Fixed(pub i32);
+
+impl Fixed {
+    pub fn to_f32(self) -> f32 {
+        self.0 as f32 / 65536.0
+    }
+}
+
+// 2.14 fixed point, used for scales in compound glyphs
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct F2Dot14(pub i16);
+
+impl F2Dot14 {
+    pub fn to_f32(self) -> f32 {
+        self.0 as f32 / 16384.0
+    }
+}
+
+// offsets are relative to whatever table (or subtable) they were read from
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Offset16(pub u16);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Offset32(pub u32);
+
+impl Offset16 {
+    pub fn to_usize(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Offset32 {
+    pub fn to_usize(self) -> usize {
+        self.0 as usize
+    }
+}
+
+// the font file itself, either read into memory or memory mapped (big CJK fonts open instantly that way).
+// a mapped file must not be modified or truncated while the FontBytes is alive, see open
+pub enum FontBytes {
+    Owned(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl FontBytes {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        #[cfg(feature = "mmap")]
+        {
+            let file = std::fs::File::open(path)?;
+            // SAFETY: the caller has to make sure the file isn't changed or truncated while it's mapped. the bounds
+            // checks don't help there, a truncated file makes reads past its new end SIGBUS and changing the bytes
+            // under a live map is UB. the viewer's asset loader (and hot reload) reads fonts into Owned bytes instead
+            Ok(FontBytes::Mapped(unsafe { memmap2::Mmap::map(&file)? }))
+        }
+        #[cfg(not(feature = "mmap"))]
+        {
+            Ok(FontBytes::Owned(std::fs::read(path)?))
+        }
+    }
+}
+
+impl Default for FontBytes {
+    fn default() -> Self {
+        FontBytes::Owned(Vec::new())
+    }
+}
+
+impl From<Vec<u8>> for FontBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        FontBytes::Owned(bytes)
+    }
+}
+
+impl Deref for FontBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FontBytes::Owned(bytes) => bytes,
+            #[cfg(feature = "mmap")]
+            FontBytes::Mapped(map) => map,
+        }
+    }
+}
+
+// reads big endian values out of a slice without copying it, positions are relative to the start of the slice
+// and nothing can read past its end, so a reader made for a table can't wander into the next one
+#[derive(Clone, Copy, Default)]
+pub struct FontReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+fn out_of_bounds(what: &str, at: usize, len: usize) -> Error {
+    Error::new(ErrorKind::UnexpectedEof, format!("{what} at {at} is past the end of the data ({len} bytes)"))
+}
+
+impl<'a> FontReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        FontReader { data, position: 0 }
+    }
+
+    pub fn read_bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let bytes = self.data
+            .get(self.position..self.position + N)
+            .ok_or_else(|| out_of_bounds("read", self.position, self.data.len()))?;
+        self.position += N;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    pub fn read_byte(&mut self) -> Result<u8> {
+        Ok(self.read_bytes::<1>()?[0])
+    }
+
+    pub fn read_i8(&mut self) -> Result<i8> {
+        Ok(i8::from_be_bytes(self.read_bytes()?))
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.read_bytes()?))
+    }
+
+    pub fn read_i16(&mut self) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.read_bytes()?))
+    }
+
+    pub fn read_u24(&mut self) -> Result<u32> {
+        let [a, b, c] = self.read_bytes()?;
+        Ok(u32::from_be_bytes([0, a, b, c]))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.read_bytes()?))
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.read_bytes()?))
+    }
+
+    pub fn read_tag(&mut self) -> Result<Tag> {
+        Ok(Tag(self.read_bytes()?))
+    }
+
+    pub fn read_fixed(&mut self) -> Result<Fixed> {
+        Ok(Fixed(self.read_i32()?))
+    }
+
+    pub fn read_f2dot14(&mut self) -> Result<F2Dot14> {
+        Ok(F2Dot14(self.read_i16()?))
+    }
+
+    pub fn read_offset16(&mut self) -> Result<Offset16> {
+        Ok(Offset16(self.read_u16()?))
+    }
+
+    // COLRv1 packs some offsets into 3 bytes
+    pub fn read_offset24(&mut self) -> Result<Offset32> {
+        Ok(Offset32(self.read_u24()?))
+    }
+
+    pub fn read_offset32(&mut self) -> Result<Offset32> {
+        Ok(Offset32(self.read_u32()?))
+    }
+
+    pub fn skip_bytes(&mut self, count: usize) -> Result<()> {
+        self.go_to(self.position + count)
+    }
+
+    pub fn go_to(&mut self, position: usize) -> Result<()> {
+        if position > self.data.len() {
+            return Err(out_of_bounds("seek", position, self.data.len()));
+        }
+        self.position = position;
+        Ok(())
+    }
+
+    pub fn get_location(&self) -> usize {
+        self.position
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    // a new reader over part of this one, for subtables and single glyphs
+    pub fn sub_reader(&self, start: usize, length: usize) -> Result<FontReader<'a>> {
+        self.data
+            .get(start..start.saturating_add(length))
+            .map(FontReader::new)
+            .ok_or_else(|| out_of_bounds("range", start, self.data.len()))
+    }
+
+    // from start to the end of the data
+    pub fn reader_from(&self, start: usize) -> Result<FontReader<'a>> {
+        self.sub_reader(start, self.data.len().saturating_sub(start))
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+}
@@ -0,0 +1,650 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::font_reader::{FontBytes, FontReader, Offset32, Tag};
+use crate::geometry::Point;
+use crate::glyph_cache::GlyphCache;
+use crate::hinting::{HintedGlyph, Hinting};
+use crate::os2::Os2;
+use crate::post::Post;
+use crate::validate::{Severity, ValidationReport};
+use crate::vertical::VerticalMetrics;
+
+pub const FONT_SIZE_CONSTANT: f32 = 85.0; // font units get scaled so one em is this many world units
+pub(crate) const MAX_COMPONENTS: usize = 1_000; // per glyph, all nesting levels together. real fonts stay in the tens
+// https://developer.apple.com/fonts/TrueType-Reference-Manual/
+// https://developer.apple.com/fonts/TrueType-Reference-Manual/RM06/Chap6glyf.html
+fn bit_is_set(flag: u8, bit: u8) -> bool {
+    (flag >> bit) & 1 == 1
+}
+
+// one flag per point, bit 3 means the next byte says how many times to repeat it
+pub(crate) fn read_flags(reader: &mut FontReader, point_count: usize) -> std::io::Result<Vec<u8>> {
+    let mut flags: Vec<u8> = Vec::with_capacity(point_count);
+
+    let mut i = 0;
+    while i < point_count {
+        i += 1;
+        let flag = reader.read_byte()?;
+        flags.push(flag);
+
+        if bit_is_set(flag, 3) {
+            for _ in 0..reader.read_byte()? {
+                flags.push(flag);
+                i += 1;
+            }
+        }
+    }
+    Ok(flags)
+}
+
+pub(crate) fn get_coordinates(
+    reader: &mut FontReader,
+    flags: &[u8],
+    font_scale: f32,
+) -> std::io::Result<Vec<(Point, bool)>> {
+    let mut coordinates: Vec<(Point, bool)> = vec![(Point::ZERO, false); flags.len()];
+
+    // FOR X
+    let mut short_vector_bit = 1;
+    let mut sign_or_skip_bit = 4;
+
+    for i in 0..coordinates.len() {
+        coordinates[i].0.x = coordinates[i.saturating_sub(1)].0.x; // all points are with respect to previous one and we need with respect to origin (0,0)
+        let flag = flags[i];
+        let on_curve = bit_is_set(flag, 0);
+        coordinates[i].1 = on_curve;
+
+        if bit_is_set(flag, short_vector_bit) {
+            let sign: f32 = if bit_is_set(flag, sign_or_skip_bit) {
+                1.0
+            } else {
+                -1.0
+            };
+            coordinates[i].0.x += (reader.read_byte()? as f32 * sign)*font_scale;
+        } else if !bit_is_set(flag, sign_or_skip_bit) {
+            coordinates[i].0.x += (reader.read_i16()? as f32)*font_scale;
+        }
+    }
+
+    // FOR Y
+    short_vector_bit = 2;
+    sign_or_skip_bit = 5;
+
+    for i in 0..coordinates.len() {
+        coordinates[i].0.y = coordinates[i.saturating_sub(1)].0.y;
+        let flag = flags[i];
+        let on_curve = bit_is_set(flag, 0);
+        coordinates[i].1 = on_curve;
+
+        if bit_is_set(flag, short_vector_bit) {
+            let sign: f32 = if bit_is_set(flag, sign_or_skip_bit) {
+                1.0
+            } else {
+                -1.0
+            };
+            coordinates[i].0.y += (reader.read_byte()? as f32 * sign)*font_scale;
+        } else if !bit_is_set(flag, sign_or_skip_bit) {
+            coordinates[i].0.y += (reader.read_i16()? as f32)*font_scale;
+        }
+    }
+
+    // move towards (0,0)
+    
+
+    Ok(coordinates)
+}
+
+#[derive(Clone, Default)]
+pub struct Glyph {
+    pub coordinates: Vec<(Point, bool)>, // bool is for on_curve parameter
+    pub contour_end_pts: Vec<u16>,
+    pub contour_coordinates: Vec<Vec<(Point, u8)>>, // these are setup in setup_implied_points
+    pub bounding_box: [f32; 4], // x_min, y_min, x_max, y_max
+}
+
+pub const HEAD: Tag = Tag::new(b"head");
+pub const MAXP: Tag = Tag::new(b"maxp");
+pub const LOCA: Tag = Tag::new(b"loca");
+pub const GLYF: Tag = Tag::new(b"glyf");
+pub const CMAP: Tag = Tag::new(b"cmap");
+pub const HHEA: Tag = Tag::new(b"hhea");
+pub const HMTX: Tag = Tag::new(b"hmtx");
+
+// tables the parser reads without checking, fonts missing any of them get rejected
+pub(crate) const REQUIRED_TABLES: [Tag; 5] = [HEAD, MAXP, CMAP, HHEA, HMTX];
+// needed too unless the font has embedded bitmaps instead (CFF .otf files have neither)
+pub(crate) const OUTLINE_TABLES: [Tag; 2] = [LOCA, GLYF];
+
+#[derive(Debug)]
+pub enum FontError {
+    Io(std::io::Error),
+    MissingTable(Tag),
+    Parse(String),
+    Invalid(ValidationReport), // only the errors, warnings dont stop a font from loading
+}
+
+impl std::fmt::Display for FontError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FontError::Io(err) => write!(f, "could not read font: {err}"),
+            FontError::MissingTable(tag) => write!(f, "font has no '{tag}' table (only TrueType outlines and embedded bitmaps are supported)"),
+            FontError::Parse(err) => write!(f, "could not parse font: {err}"),
+            FontError::Invalid(report) => write!(f, "font failed validation:\n{report}"),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+impl From<std::io::Error> for FontError {
+    fn from(err: std::io::Error) -> Self {
+        FontError::Io(err)
+    }
+}
+
+// one entry of the table directory, offset is from the start of the file
+#[derive(Clone, Copy, Debug)]
+pub struct TableRecord {
+    pub checksum: u32,
+    pub offset: Offset32,
+    pub length: u32,
+}
+
+#[derive(Default)]
+pub struct FontData {
+    pub data: FontBytes,
+    pub font_table: HashMap<Tag, TableRecord>,
+    pub glyph_locations: Vec<usize>, // num_glyphs + 1 offsets into glyf, glyph i is glyph_locations[i]..glyph_locations[i + 1]
+    pub num_glyphs: usize,
+    pub glyph_cache: Mutex<GlyphCache>, // outlines are only parsed when they are asked for, see glyph()
+    pub hinting: OnceLock<Option<Hinting>>, // fpgm, prep and cvt, read the first time a glyph gets hinted
+    pub hinted_glyph_cache: Mutex<GlyphCache<(usize, u16), HintedGlyph>>, // by glyph and ppem, see hinted_glyph()
+    pub unicodes_to_index: HashMap<u32, usize>,
+    pub glyph_spaces: Vec<f32>,
+    pub font_scale: f32,
+    pub line_height: f32, // ascent - descent + line_gap
+    pub ascent: f32, // baseline to the top of the line, scaled like the outlines
+    pub descent: f32, // negative, baseline to the bottom
+    pub line_gap: f32,
+    pub os2: Option<Os2>,
+    pub post: Option<Post>,
+    pub glyph_names: OnceLock<Vec<String>>, // from post, read the first time glyph_name is called
+    pub vertical: Option<VerticalMetrics>, // from vhea/vmtx, see vertical_advance for fonts without them
+    pub vertical_substitutions: HashMap<usize, usize>, // GSUB vert, glyph index to its vertical form
+    pub face_index: u32, // which font to use out of a collection (.ttc), ignored for single fonts
+    pub report: ValidationReport, // warnings from validate() when the font was parsed
+}
+
+impl FontData {
+    // the whole pipeline, what most users want: FontData::parse(std::fs::read(path)?, 0)
+    pub fn parse(bytes: impl Into<FontBytes>, face_index: u32) -> Result<Self, FontError> {
+        let mut font_data = FontData {
+            data: bytes.into(),
+            face_index,
+            ..Default::default()
+        };
+
+        font_data.get_lookup_table()?;
+        if let Some(missing) = REQUIRED_TABLES.into_iter().find(|tag| !font_data.font_table.contains_key(tag)) {
+            return Err(FontError::MissingTable(missing));
+        }
+        if let Some(missing) = OUTLINE_TABLES.into_iter().find(|tag| !font_data.font_table.contains_key(tag) && !font_data.has_bitmaps()) {
+            return Err(FontError::MissingTable(missing));
+        }
+        font_data.get_glyph_location()?;
+        font_data.get_font_scale()?;
+        font_data.map_glyph_to_unicode()?;
+        font_data.get_glyph_spacings()?;
+        font_data.get_os2()?;
+        font_data.get_post()?;
+        font_data.get_vertical_metrics()?;
+
+        let mut report = font_data.validate();
+        if report.has_errors() {
+            report.issues.retain(|issue| issue.severity == Severity::Error);
+            return Err(FontError::Invalid(report));
+        }
+        font_data.report = report;
+        Ok(font_data)
+    }
+
+    // memory maps the file when the mmap feature is on, the file must not be changed or truncated while the font is
+    // alive then. use parse(std::fs::read(path)?, ..) for files that might be edited in place
+    pub fn open(path: impl AsRef<Path>, face_index: u32) -> Result<Self, FontError> {
+        Self::parse(FontBytes::open(path)?, face_index)
+    }
+
+    // a reader that only sees this table, bounded by the length in the table directory
+    pub fn table(&self, tag: Tag) -> Result<FontReader<'_>, FontError> {
+        let record = self.font_table.get(&tag).ok_or(FontError::MissingTable(tag))?;
+        FontReader::new(&self.data)
+            .sub_reader(record.offset.to_usize(), record.length as usize)
+            .map_err(|_| FontError::Parse(format!("'{tag}' table is out of bounds")))
+    }
+
+    //https://developer.apple.com/fonts/TrueType-Reference-Manual/RM06/Chap6.html
+    pub fn get_lookup_table(&mut self) -> std::io::Result<()> {
+        let mut reader = FontReader::new(&self.data);
+
+        // https://learn.microsoft.com/en-us/typography/opentype/spec/otff#font-collections
+        if reader.read_tag()? == Tag::new(b"ttcf") {
+            reader.skip_bytes(4)?; // skip version
+            let num_fonts = reader.read_u32()?;
+            if self.face_index >= num_fonts {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("face {} requested but the collection only has {num_fonts}", self.face_index),
+                ));
+            }
+            reader.skip_bytes(self.face_index as usize * 4)?;
+            let table_directory = reader.read_offset32()?;
+            reader.go_to(table_directory.to_usize())?; // table offsets are from the start of the file so the rest reads the same
+        } else {
+            reader.go_to(0)?;
+        }
+
+        reader.skip_bytes(4)?; // skip scaler type
+        let n_tables = reader.read_u16()?;
+        reader.skip_bytes(6)?; // skip searchRange, entrySelector and rangeShift
+
+        let mut table_data: HashMap<Tag, TableRecord> = HashMap::with_capacity(n_tables as usize);
+        for _ in 0..n_tables {
+            let tag = reader.read_tag()?;
+            let checksum = reader.read_u32()?;
+            let offset = reader.read_offset32()?;
+            let length = reader.read_u32()?;
+            table_data.insert(tag, TableRecord { checksum, offset, length });
+        }
+
+        self.font_table = table_data;
+        Ok(())
+    }
+
+    fn read_num_glyphs(&self) -> Result<usize, FontError> {
+        let mut maxp = self.table(MAXP)?;
+        maxp.skip_bytes(4)?; // skip version
+        Ok(maxp.read_u16()? as usize)
+    }
+
+    pub fn get_glyph_location(&mut self) -> Result<(), FontError> {
+        let num_glyphs = self.read_num_glyphs()?;
+        self.num_glyphs = num_glyphs;
+
+        if !self.font_table.contains_key(&LOCA) {
+            return Ok(()); // bitmap only font
+        }
+
+        let mut head = self.table(HEAD)?;
+        head.go_to(50)?; // skip version, fontRevision .... till fontDirectionHint
+        let is_two_byte_entry = head.read_i16()? == 0; // 0 is short (2 byte) offset, 1 is long (4 byte) (indexToLocFormat)
+
+        let mut loca = self.table(LOCA)?;
+        let mut glyph_locations = Vec::with_capacity(num_glyphs + 1);
+        for _ in 0..=num_glyphs {
+            let glyph_offset = if is_two_byte_entry {
+                loca.read_u16()? as usize * 2 // two byte format has halved offset so we multiply by 2
+            } else {
+                loca.read_u32()? as usize
+            };
+
+            glyph_locations.push(glyph_offset);
+        }
+
+        self.glyph_locations = glyph_locations;
+        Ok(())
+    }
+
+    // the bytes of one glyph, empty for glyphs without an outline (like space)
+    pub(crate) fn glyph_reader<'a>(&self, glyf: &FontReader<'a>, glyph_index: usize) -> Result<FontReader<'a>, FontError> {
+        let (Some(start), Some(end)) = (self.glyph_locations.get(glyph_index), self.glyph_locations.get(glyph_index + 1)) else {
+            return Err(FontError::Parse(format!("glyph {glyph_index} has no entry in 'loca'")));
+        };
+        glyf.sub_reader(*start, end.saturating_sub(*start))
+            .map_err(|_| FontError::Parse(format!("glyph {glyph_index} is out of the 'glyf' table")))
+    }
+
+    pub fn get_font_scale(&mut self) -> Result<(), FontError> {
+        let mut head = self.table(HEAD)?;
+        head.go_to(18)?; // unitsPerEm
+        self.font_scale = FONT_SIZE_CONSTANT/head.read_u16()? as f32;
+        Ok(())
+    }
+
+    // parsed on first use and kept in the glyph cache, fonts with tens of thousands of glyphs
+    // only ever parse the few hundred that actually get shown
+    pub fn glyph(&self, glyph_index: usize) -> Result<Arc<Glyph>, FontError> {
+        if let Some(glyph) = self.glyph_cache.lock().unwrap().get(glyph_index) {
+            return Ok(glyph);
+        }
+
+        let mut glyph = self.parse_glyph(glyph_index, 0, &mut 0)?;
+        setup_implied_points(std::slice::from_mut(&mut glyph));
+        let glyph = Arc::new(glyph);
+        self.glyph_cache.lock().unwrap().insert(glyph_index, glyph.clone());
+        Ok(glyph)
+    }
+
+    // how many parsed glyphs are kept around, DEFAULT_GLYPH_CACHE_CAPACITY unless changed. hinted ones get the same limit
+    pub fn set_glyph_cache_capacity(&self, capacity: usize) {
+        self.glyph_cache.lock().unwrap().set_capacity(capacity);
+        self.hinted_glyph_cache.lock().unwrap().set_capacity(capacity);
+    }
+
+    // the raw outline (coordinates + contour ends), compound glyphs get their components parsed
+    // and stitched in here. depth guards against components that (directly or not) include themselves,
+    // components counts every one of them at every level so a compound reusing compounds can't blow up
+    pub(crate) fn parse_glyph(&self, glyph_index: usize, depth: usize, components: &mut usize) -> Result<Glyph, FontError> {
+        const MAX_COMPONENT_DEPTH: usize = 8;
+        if depth > MAX_COMPONENT_DEPTH {
+            return Err(FontError::Parse(format!("glyph {glyph_index} nests components too deep")));
+        }
+
+        if self.glyph_locations.is_empty() || !self.font_table.contains_key(&GLYF) {
+            return Ok(Glyph::default()); // bitmap only fonts have nothing to outline, see bitmap_glyph
+        }
+
+        let font_scale = self.font_scale;
+        let glyf = self.table(GLYF)?;
+        let mut reader = self.glyph_reader(&glyf, glyph_index)?;
+        if reader.is_empty() {
+            return Ok(Glyph::default());
+        }
+
+        let n_contours = reader.read_i16()?;
+        let (x_min, y_min, x_max, y_max) = (reader.read_i16()? as f32*font_scale,reader.read_i16()? as f32*font_scale,reader.read_i16()? as f32*font_scale,reader.read_i16()? as f32*font_scale);
+
+        if n_contours < 0 { // COMPOUND GLYPH
+            let mut new_coordinates: Vec<(Point, bool)> = Vec::with_capacity(105);
+            let mut new_contour_end_pts: Vec<u16> = Vec::with_capacity(5); 
+            let mut last_end_point: u32 = 0;
+            loop { 
+                let flags = reader.read_u16()? as u8; //  since i do need the bits above 8 just make it one byte
+                let component_index = reader.read_u16()? as usize;
+
+                let arg_are_2_bytes = bit_is_set(flags, 0);
+                let args_are_xy_values = bit_is_set(flags, 1);
+                let x_y_scale_same = bit_is_set(flags, 3);
+                let x_y_scale_not_same = bit_is_set(flags, 6);
+                let is_2by2 = bit_is_set(flags, 7);
+
+                if !args_are_xy_values {
+                    // have not really encountered fonts that use these yet so ill implement if i find one :3
+                    return Err(FontError::Parse(format!("glyph {glyph_index} places its components by point numbers, which isnt supported")));
+                }
+
+                let x_offset = if arg_are_2_bytes {reader.read_i16()? as f32} else {reader.read_i8()? as f32};
+                let y_offset = if arg_are_2_bytes {reader.read_i16()? as f32} else {reader.read_i8()? as f32};
+
+                // [a11, a12, a21, a22], plain scales just fill the diagonal
+                let scale = if is_2by2 {
+                    [reader.read_f2dot14()?.to_f32(), reader.read_f2dot14()?.to_f32(), reader.read_f2dot14()?.to_f32(), reader.read_f2dot14()?.to_f32()]
+                } else if x_y_scale_same {
+                    let s = reader.read_f2dot14()?.to_f32();
+                    [s, 0.0, 0.0, s]
+                } else if x_y_scale_not_same {
+                    [reader.read_f2dot14()?.to_f32(), 0.0, 0.0, reader.read_f2dot14()?.to_f32()]
+                } else {
+                    [1.0, 0.0, 0.0, 1.0]
+                };
+
+                *components += 1;
+                if *components > MAX_COMPONENTS {
+                    return Err(FontError::Parse(format!("glyph {glyph_index} has too many components")));
+                }
+                let component = self.parse_glyph(component_index, depth + 1, components)?;
+                let offset_x = x_offset*font_scale;
+                let offset_y = y_offset*font_scale;
+                let [a11, a12, a21, a22] = scale;
+                component.coordinates.iter().for_each(|f| new_coordinates.push((Point::new(f.0.x*a11 + f.0.y*a12 + offset_x, f.0.x*a21 + f.0.y*a22 + offset_y), f.1)));
+                // point numbers are u16, a compound with more points than that can't be addressed
+                for end in component.contour_end_pts.iter() {
+                    let end = u16::try_from(*end as u32 + last_end_point)
+                        .map_err(|_| FontError::Parse(format!("glyph {glyph_index} has too many points in its components")))?;
+                    new_contour_end_pts.push(end);
+                }
+                last_end_point += component.contour_end_pts.last().map_or(0, |end| *end as u32 + 1);
+
+                if !bit_is_set(flags, 5) {
+                    break;
+                }
+            }
+
+            return Ok(Glyph { coordinates: new_coordinates, contour_end_pts: new_contour_end_pts, contour_coordinates: Vec::with_capacity(5), bounding_box: [x_min,y_min,x_max,y_max] });
+        }
+
+        // SIMPLE GLYPH
+        let n_contours = n_contours as usize;
+        let mut contour_end_pts = Vec::with_capacity(n_contours);
+        for _ in 0..n_contours {
+            contour_end_pts.push(reader.read_u16()?);
+        }
+        // setup_implied_points slices the points by these, so they have to go up
+        if contour_end_pts.windows(2).any(|ends| ends[0] >= ends[1]) {
+            return Err(FontError::Parse(format!("glyph {glyph_index} has contour end points out of order")));
+        }
+
+        let instructions_length = reader.read_u16()?;
+        reader.skip_bytes(instructions_length as usize)?; // skip instructions 
+
+        let flags = read_flags(&mut reader, *contour_end_pts.last().unwrap_or(&0) as usize + 1)?;
+        let coordinates = get_coordinates(&mut reader, &flags, font_scale)?;
+        Ok(Glyph { coordinates, contour_end_pts, contour_coordinates: Vec::with_capacity(n_contours), bounding_box: [x_min,y_min,x_max,y_max] })
+    }
+
+    // https://developer.apple.com/fonts/TrueType-Reference-Manual/RM06/Chap6cmap.html
+    pub fn map_glyph_to_unicode(
+        &mut self
+    ) -> Result<(), FontError> {
+        let cmap = self.table(CMAP)?;
+        let mut reader = cmap;
+
+        reader.skip_bytes(2)?; // skip version
+        let n_subtables = reader.read_u16()?;
+
+        let mut cmap_subtable_offset = None;
+        for _ in 0..(n_subtables as usize) {
+            let platform_id = reader.read_u16()?;
+            let platform_specific_id = reader.read_u16()?;
+            let offset = reader.read_offset32()?;
+
+            if platform_id == 0 { // 0 is unicode
+                if platform_specific_id == 4 { // unicode 2.0 (non bmp allowed)
+                    cmap_subtable_offset = Some(offset);
+                }
+                if platform_specific_id == 3 && cmap_subtable_offset.is_none() { // unicode 2.0 (bmp only)
+                    cmap_subtable_offset = Some(offset);
+                }
+            }
+        }
+        
+        let Some(cmap_subtable_offset) = cmap_subtable_offset else {
+            return Err(FontError::Parse("font does not have a unicode character map".to_string()));
+        };
+
+        let mut reader = cmap.reader_from(cmap_subtable_offset.to_usize())?; // offsets inside the subtable are from its start
+
+        let mut unicode_to_index_map: HashMap<u32, usize> = HashMap::with_capacity(self.num_glyphs);
+        
+        let format = reader.read_u16()?;
+        if format != 4 && format != 12 {
+            return Err(FontError::Parse(format!("character map format {format} is not supported")));
+        } else if format == 12 {
+            reader.skip_bytes(10)?; // skip reserved, length, language
+            let n_groups = reader.read_u32()?;
+            for _ in 0..n_groups {
+                let start_char_code = reader.read_u32()?;
+                let end_char_code = reader.read_u32()?;
+                let start_glyph_code = reader.read_u32()?;
+
+                // unicode stops at 0x10FFFF, anything past that (or backwards) would only be a huge loop
+                if end_char_code < start_char_code || end_char_code > 0x10FFFF {
+                    return Err(FontError::Parse(format!("cmap group {start_char_code:#X}..{end_char_code:#X} is out of range")));
+                }
+                for char_code in start_char_code..=end_char_code {
+                    let glyph_index = (start_glyph_code as usize).checked_add((char_code - start_char_code) as usize)
+                        .ok_or_else(|| FontError::Parse(format!("cmap group {start_char_code:#X}..{end_char_code:#X} runs past the glyph ids")))?;
+                    unicode_to_index_map.insert(char_code, glyph_index);
+                }
+            }
+        } else if format == 4 {
+            reader.skip_bytes(4)?; // skip length, language
+            let seg_count = (reader.read_u16()?/2) as usize;
+            reader.skip_bytes(6)?; // skip searchRange, entrySelector, rangeShift
+            
+            let mut end_codes: Vec<u32> = Vec::with_capacity(seg_count);
+            for _ in 0..seg_count {
+                end_codes.push(reader.read_u16()? as u32);
+            }
+
+            reader.skip_bytes(2)?; // skip reservedPad
+
+            let mut start_codes: Vec<u32> = Vec::with_capacity(seg_count);
+            for _ in 0..seg_count {
+                start_codes.push(reader.read_u16()? as u32);
+            }
+
+            let mut id_deltas: Vec<u32> = Vec::with_capacity(seg_count);
+            for _ in 0..seg_count {
+                id_deltas.push(reader.read_u16()? as u32);
+            }
+            
+            let mut id_range_offsets: Vec<(usize, usize)> = Vec::with_capacity(seg_count); // (current_location, offset)
+            for _ in 0..seg_count {
+                id_range_offsets.push((reader.get_location(), reader.read_offset16()?.to_usize()));
+            }
+            
+            for i in 0..start_codes.len() {
+                let end_code = end_codes[i];
+                let mut curr_code = start_codes[i];
+
+                while curr_code <= end_code {
+                    let mut glyph_index = 0;
+
+                    if id_range_offsets[i].1 == 0 {
+                        glyph_index = (curr_code + id_deltas[i]) % 65536;
+                    } else {
+                        let range_offset_location = id_range_offsets[i].0 + id_range_offsets[i].1;
+                        let glyph_index_address = range_offset_location + (2 * (curr_code - start_codes[i])) as usize;
+
+                        let reader_prev_location = reader.get_location();
+                        reader.go_to(glyph_index_address)?;
+
+                        let glyph_index_offset = reader.read_u16()? as u32;
+                        reader.go_to(reader_prev_location)?;
+
+                        if glyph_index_offset != 0 {
+                            glyph_index = (glyph_index_offset + id_deltas[i]) % 65536;
+                        }
+                    }
+
+                    unicode_to_index_map.insert(curr_code, glyph_index as usize);
+                    curr_code += 1;
+                }
+            }
+        }       
+
+        self.unicodes_to_index = unicode_to_index_map;
+        Ok(())
+    }
+
+    pub fn get_glyph_spacings(&mut self) -> Result<(), FontError> {
+        let mut hhea = self.table(HHEA)?;
+        hhea.go_to(4)?;
+        
+        let ascent = hhea.read_i16()? as f32;
+        let descent = hhea.read_i16()? as f32;
+        let line_gap = hhea.read_i16()? as f32;
+        
+        hhea.skip_bytes(24)?;
+        let num_long_hor_metrics = hhea.read_u16()? as usize;
+        if num_long_hor_metrics == 0 {
+            return Err(FontError::Parse("'hhea' has no horizontal metrics".to_string()));
+        }
+
+        let mut hmtx = self.table(HMTX)?;
+        let mut advance_widths: Vec<f32> = Vec::with_capacity(self.num_glyphs);
+
+        for _ in 0..num_long_hor_metrics {
+            advance_widths.push(hmtx.read_u16()? as f32);
+            hmtx.skip_bytes(2)?;
+        }
+
+        // some fonts include a run of mono-spaced glyphs at the end
+        // they all share the same advanced width value as whatever we read last
+        let num_monospaced = self.num_glyphs.saturating_sub(num_long_hor_metrics);
+        let monospace_aw = advance_widths[num_long_hor_metrics - 1];
+        
+        for _ in 0..num_monospaced {
+            advance_widths.push(monospace_aw);
+        }
+        
+        self.glyph_spaces = advance_widths;
+        self.set_line_metrics(ascent, descent, line_gap);
+        Ok(())
+    }
+
+    // from font units
+    pub(crate) fn set_line_metrics(&mut self, ascent: f32, descent: f32, line_gap: f32) {
+        self.ascent = ascent * self.font_scale;
+        self.descent = descent * self.font_scale;
+        self.line_gap = line_gap.max(0.0) * self.font_scale;
+        self.line_height = self.ascent - self.descent + self.line_gap;
+    }
+}
+
+pub fn setup_implied_points(glyph_data: &mut [Glyph]) {
+    for glyph in glyph_data.iter_mut() {
+        let (glyph_coords, contour_end_points) = (&glyph.coordinates, &glyph.contour_end_pts);
+        
+        let mut contour_start = 0;
+        for contour_end in contour_end_points.iter() {
+            /*
+            first we loop over the points in the contour and if two consecutive points are oncurve or offcurve, we insert
+            an implied offcurve or oncurve point which will help us control the bezier curve
+            */
+
+            /*
+            found this bug with JETBRAINS MONO at the moment
+            BUG: some glyphs like Ŀ ŀ, their dot contour is just a single point, not a set of points so gotta hardcode a dot i guess
+            well thats what i hope so and its not a parsing issue.
+
+            therefore im usize:min(ing) contour_end for now because it leads to index error
+            */
+            let contour_end = usize::min(*contour_end as usize + 1, glyph_coords.len()); 
+            
+            let old_contour = &glyph_coords[contour_start..(contour_end)];
+            let oc_size = old_contour.len();
+
+            let mut first_oncurve_offset = 0; // sometimes the first point isnt on_curve
+            while first_oncurve_offset < oc_size {
+                if old_contour[first_oncurve_offset].1 {
+                    break;
+                }
+                first_oncurve_offset += 1;
+            }
+
+            let mut contour_with_implied_points: Vec<(Point, u8)> = Vec::with_capacity(oc_size);
+
+            let mut i = 0;
+            while i < oc_size {
+                let a = old_contour[(i + first_oncurve_offset) % oc_size];
+                let b = old_contour[(i + first_oncurve_offset + 1) % oc_size];
+
+                contour_with_implied_points.push((a.0, if a.1 {0} else {1})); // 0 MEANS ONCURVE 1 MEANS OFFCURVE
+                if a.1 == b.1 {
+                    // both points either on or off curve, then we insert a midpoint as a control point for bezier
+                    contour_with_implied_points.push((a.0.midpoint(b.0),2)); // 2 means INSERTED POINT
+                }
+                i += 1;
+            }
+
+            glyph.contour_coordinates.push(contour_with_implied_points);
+            contour_start = contour_end;
+        }
+        glyph.coordinates = Vec::new();
+        glyph.contour_end_pts = Vec::new(); 
+        // they are dead weight
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::font_table_parser::Glyph;
+
+pub const DEFAULT_GLYPH_CACHE_CAPACITY: usize = 1024;
+
+// parsed glyphs by index, when it's full the one that was used longest ago gets dropped.
+// hinted glyphs go in one keyed by (index, ppem)
+pub struct GlyphCache<K = usize, G = Glyph> {
+    glyphs: HashMap<K, (Arc<G>, u64)>, // (glyph, last used)
+    capacity: usize,
+    tick: u64,
+}
+
+impl<K: Copy + Eq + Hash, G> Default for GlyphCache<K, G> {
+    fn default() -> Self {
+        GlyphCache::new(DEFAULT_GLYPH_CACHE_CAPACITY)
+    }
+}
+
+impl<K: Copy + Eq + Hash, G> GlyphCache<K, G> {
+    pub fn new(capacity: usize) -> Self {
+        GlyphCache {
+            glyphs: HashMap::with_capacity(capacity),
+            capacity: capacity.max(1),
+            tick: 0,
+        }
+    }
+
+    pub fn get(&mut self, glyph_index: K) -> Option<Arc<G>> {
+        self.tick += 1;
+        let (glyph, last_used) = self.glyphs.get_mut(&glyph_index)?;
+        *last_used = self.tick;
+        Some(glyph.clone())
+    }
+
+    pub fn insert(&mut self, glyph_index: K, glyph: Arc<G>) {
+        if !self.glyphs.contains_key(&glyph_index) {
+            self.evict_down_to(self.capacity - 1);
+        }
+        self.tick += 1;
+        self.glyphs.insert(glyph_index, (glyph, self.tick));
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.evict_down_to(self.capacity);
+    }
+
+    fn evict_down_to(&mut self, len: usize) {
+        while self.glyphs.len() > len {
+            let Some(oldest) = self.glyphs.iter().min_by_key(|(_, (_, last_used))| *last_used).map(|(index, _)| *index) else {
+                break;
+            };
+            self.glyphs.remove(&oldest);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::font_reader::Tag;
+use crate::font_table_parser::{get_coordinates, read_flags, setup_implied_points, FontData, FontError, Glyph, FONT_SIZE_CONSTANT, GLYF, HEAD, HHEA, HMTX, MAXP, MAX_COMPONENTS};
+use crate::geometry::Point;
+use crate::interpreter::{Interpreter, InterpreterLimits, Vector, Zone};
+
+pub const FPGM: Tag = Tag::new(b"fpgm");
+pub const PREP: Tag = Tag::new(b"prep");
+pub const CVT: Tag = Tag::new(b"cvt ");
+
+const MAX_HINTED_SIZES: usize = 32; // ppems that keep what fpgm and prep left behind, zooming goes through a lot of them
+const PHANTOM_POINTS: usize = 4; // left and right side bearing, top and bottom origin
+
+// component flags that matter for hinting, see parse_glyph for the rest
+const ROUND_XY_TO_GRID: u16 = 1 << 2;
+const MORE_COMPONENTS: u16 = 1 << 5;
+const WE_HAVE_INSTRUCTIONS: u16 = 1 << 8;
+const USE_MY_METRICS: u16 = 1 << 9;
+
+// a glyph grid fitted at one ppem, the outline is scaled like the unhinted ones so it draws the same way
+#[derive(Clone, Default)]
+pub struct HintedGlyph {
+    pub glyph: Arc<Glyph>,
+    pub advance: f32, // the hinted advance width, whole pixels at the ppem it was hinted for. scaled like glyph_spaces * font_scale
+}
+
+// fpgm, prep and cvt, read the first time something gets hinted
+pub struct Hinting {
+    fpgm: Arc<[u8]>,
+    prep: Arc<[u8]>,
+    cvt: Vec<i16>, // font units
+    limits: InterpreterLimits,
+    units_per_em: u16,
+    sizes: Mutex<HashMap<u16, Result<Arc<Interpreter>, String>>>, // after fpgm and prep ran at that ppem
+}
+
+// shared by every component of one glyph, a compound that uses a heavily instructed glyph over and over
+// (at every level) runs out of it instead of multiplying the work
+#[derive(Default)]
+struct HintBudget {
+    components: usize, // against MAX_COMPONENTS, like parse_glyph
+    instructions: usize, // against the interpreter's MAX_INSTRUCTIONS
+}
+
+impl FontData {
+    // https://learn.microsoft.com/en-us/typography/opentype/spec/ttinst
+    // None for fonts without TrueType outlines (or with a 0.5 maxp, which has no room for the interpreter's limits)
+    pub fn hinting(&self) -> Option<&Hinting> {
+        self.hinting.get_or_init(|| self.read_hinting().ok().flatten()).as_ref()
+    }
+
+    fn read_hinting(&self) -> Result<Option<Hinting>, FontError> {
+        if self.glyph_locations.is_empty() || !self.font_table.contains_key(&GLYF) {
+            return Ok(None);
+        }
+        let mut maxp = self.table(MAXP)?;
+        if maxp.read_fixed()?.0 != 0x00010000 {
+            return Ok(None);
+        }
+        maxp.go_to(16)?; // maxTwilightPoints
+        let max_twilight_points = maxp.read_u16()? as usize;
+        let max_storage = maxp.read_u16()? as usize;
+        let max_function_defs = maxp.read_u16()? as usize;
+        maxp.skip_bytes(2)?; // maxInstructionDefs, IDEFs go in a map anyway
+        let max_stack_elements = maxp.read_u16()? as usize;
+        let limits = InterpreterLimits { max_twilight_points, max_storage, max_function_defs, max_stack_elements };
+
+        // all three are optional, plenty of fonts only have glyph programs
+        let program = |tag: Tag| -> Result<Arc<[u8]>, FontError> {
+            if !self.font_table.contains_key(&tag) {
+                return Ok(Arc::from([]));
+            }
+            Ok(Arc::from(self.table(tag)?.data()))
+        };
+        let mut cvt = Vec::new();
+        if self.font_table.contains_key(&CVT) {
+            let mut reader = self.table(CVT)?;
+            for _ in 0..reader.len() / 2 {
+                cvt.push(reader.read_i16()?);
+            }
+        }
+
+        let mut head = self.table(HEAD)?;
+        head.go_to(18)?; // unitsPerEm
+        let units_per_em = head.read_u16()?;
+        Ok(Some(Hinting { fpgm: program(FPGM)?, prep: program(PREP)?, cvt, limits, units_per_em, sizes: Mutex::default() }))
+    }
+
+    // the interpreter with fpgm and prep already run at this ppem, they only run once per size
+    fn sized_interpreter(&self, hinting: &Hinting, ppem: u16) -> Result<Arc<Interpreter>, FontError> {
+        let mut sizes = hinting.sizes.lock().unwrap();
+        if !sizes.contains_key(&ppem) {
+            if sizes.len() >= MAX_HINTED_SIZES {
+                sizes.clear();
+            }
+            let mut interpreter = Interpreter::new(ppem, hinting.units_per_em, &hinting.cvt, hinting.limits);
+            let prepared = interpreter
+                .run_font_program(hinting.fpgm.clone())
+                .and_then(|_| interpreter.run_control_value_program(hinting.prep.clone()))
+                .map(|_| Arc::new(interpreter))
+                .map_err(|err| err.to_string());
+            sizes.insert(ppem, prepared);
+        }
+        sizes[&ppem].clone().map_err(FontError::Parse)
+    }
+
+    // runs the glyph's instructions at ppem (pixels per em), every time it's called.
+    // errors when the font can't be hinted or one of its programs fails, hinted_glyph falls back to the plain outline then
+    pub fn hint_glyph(&self, glyph_index: usize, ppem: u16) -> Result<HintedGlyph, FontError> {
+        let ppem = ppem.max(1);
+        let Some(hinting) = self.hinting() else {
+            return Err(FontError::Parse("font has no TrueType outlines to hint".to_string()));
+        };
+        let interpreter = self.sized_interpreter(hinting, ppem)?;
+        let zone = self.hint_outline(&interpreter, glyph_index, 0, &mut HintBudget::default())?;
+
+        // back from 26.6 pixels to the same units as the unhinted outlines, with the hinted left side bearing point as the origin
+        let to_scaled = FONT_SIZE_CONSTANT / (ppem as f32 * 64.0);
+        let outline_len = zone.cur.len() - PHANTOM_POINTS;
+        let (origin, right) = (zone.cur[outline_len].0, zone.cur[outline_len + 1].0);
+        let coordinates: Vec<(Point, bool)> = zone.cur[..outline_len]
+            .iter()
+            .zip(zone.on_curve.iter())
+            .map(|((x, y), on_curve)| (Point::new(x.wrapping_sub(origin) as f32 * to_scaled, *y as f32 * to_scaled), *on_curve))
+            .collect();
+
+        let mut bounding_box = [0.0; 4];
+        if let Some((first, _)) = coordinates.first() {
+            bounding_box = coordinates.iter().fold([first.x, first.y, first.x, first.y], |[x_min, y_min, x_max, y_max], (point, _)| {
+                [x_min.min(point.x), y_min.min(point.y), x_max.max(point.x), y_max.max(point.y)]
+            });
+        }
+        let contour_end_pts = zone.contour_ends.iter().map(|end| *end as u16).collect();
+        let mut glyph = Glyph { coordinates, contour_end_pts, contour_coordinates: Vec::with_capacity(zone.contour_ends.len()), bounding_box };
+        setup_implied_points(std::slice::from_mut(&mut glyph));
+        Ok(HintedGlyph { glyph: Arc::new(glyph), advance: right.wrapping_sub(origin) as f32 * to_scaled })
+    }
+
+    // hint_glyph through its own cache, keyed by glyph and ppem. fonts that can't be hinted (or whose instructions fail)
+    // get the plain outline and advance, so this only errors when the glyph itself doesn't parse
+    pub fn hinted_glyph(&self, glyph_index: usize, ppem: u16) -> Result<Arc<HintedGlyph>, FontError> {
+        if let Some(hinted) = self.hinted_glyph_cache.lock().unwrap().get((glyph_index, ppem)) {
+            return Ok(hinted);
+        }
+
+        let hinted = match self.hint_glyph(glyph_index, ppem) {
+            Ok(hinted) => hinted,
+            Err(_) => HintedGlyph {
+                glyph: self.glyph(glyph_index)?,
+                advance: self.glyph_spaces.get(glyph_index).copied().unwrap_or_default() * self.font_scale,
+            },
+        };
+        let hinted = Arc::new(hinted);
+        self.hinted_glyph_cache.lock().unwrap().insert((glyph_index, ppem), hinted.clone());
+        Ok(hinted)
+    }
+
+    // the glyph's points in 26.6 with its 4 phantom points at the end, hinted by its own program.
+    // compound glyphs hint their components first and then run their own instructions over all of them
+    fn hint_outline(&self, interpreter: &Interpreter, glyph_index: usize, depth: usize, budget: &mut HintBudget) -> Result<Zone, FontError> {
+        const MAX_COMPONENT_DEPTH: usize = 8;
+        if depth > MAX_COMPONENT_DEPTH {
+            return Err(FontError::Parse(format!("glyph {glyph_index} nests components too deep")));
+        }
+
+        let glyf = self.table(GLYF)?;
+        let mut reader = self.glyph_reader(&glyf, glyph_index)?;
+        if reader.is_empty() {
+            let phantoms = self.phantom_points(glyph_index, [0; 4])?;
+            return run_glyph_program(interpreter, outline_zone(interpreter, Vec::new(), Vec::new(), phantoms), &[], budget);
+        }
+
+        let n_contours = reader.read_i16()?;
+        let bounding_box = [reader.read_i16()?, reader.read_i16()?, reader.read_i16()?, reader.read_i16()?];
+
+        if n_contours >= 0 {
+            let mut contour_ends = Vec::with_capacity(n_contours as usize);
+            for _ in 0..n_contours {
+                contour_ends.push(reader.read_u16()? as usize);
+            }
+            // SHC and IUP walk the contours by these, they have to go up or they'd run past the points
+            if contour_ends.windows(2).any(|ends| ends[0] >= ends[1]) {
+                return Err(FontError::Parse(format!("glyph {glyph_index} has contour end points out of order")));
+            }
+            let instructions_length = reader.read_u16()? as usize;
+            let instructions = reader.sub_reader(reader.get_location(), instructions_length)?.data();
+            reader.skip_bytes(instructions_length)?;
+
+            let flags = read_flags(&mut reader, contour_ends.last().map_or(0, |end| end + 1))?;
+            let points = get_coordinates(&mut reader, &flags, 1.0)?
+                .into_iter()
+                .map(|(point, on_curve)| ((point.x as i32, point.y as i32), on_curve))
+                .collect();
+            let phantoms = self.phantom_points(glyph_index, bounding_box)?;
+            return run_glyph_program(interpreter, outline_zone(interpreter, points, contour_ends, phantoms), instructions, budget);
+        }
+
+        // COMPOUND GLYPH, the components are hinted on their own and put together where they belong
+        let mut zone = Zone::default();
+        let mut component_metrics = None;
+        loop {
+            let flags = reader.read_u16()?;
+            let component_index = reader.read_u16()? as usize;
+            if flags & 2 == 0 {
+                return Err(FontError::Parse(format!("glyph {glyph_index} places its components by point numbers, which isnt supported")));
+            }
+            let (x_offset, y_offset) = if flags & 1 != 0 {
+                (reader.read_i16()? as i32, reader.read_i16()? as i32)
+            } else {
+                (reader.read_i8()? as i32, reader.read_i8()? as i32)
+            };
+            let scale = if flags & (1 << 7) != 0 {
+                [reader.read_f2dot14()?.to_f32(), reader.read_f2dot14()?.to_f32(), reader.read_f2dot14()?.to_f32(), reader.read_f2dot14()?.to_f32()]
+            } else if flags & (1 << 3) != 0 {
+                let s = reader.read_f2dot14()?.to_f32();
+                [s, 0.0, 0.0, s]
+            } else if flags & (1 << 6) != 0 {
+                [reader.read_f2dot14()?.to_f32(), 0.0, 0.0, reader.read_f2dot14()?.to_f32()]
+            } else {
+                [1.0, 0.0, 0.0, 1.0]
+            };
+
+            budget.components += 1;
+            if budget.components > MAX_COMPONENTS {
+                return Err(FontError::Parse(format!("glyph {glyph_index} has too many components")));
+            }
+            let component = self.hint_outline(interpreter, component_index, depth + 1, budget)?;
+            let outline_len = component.cur.len() - PHANTOM_POINTS;
+            if flags & USE_MY_METRICS != 0 {
+                component_metrics = Some(component.orus[outline_len..].to_vec());
+            }
+
+            let transform = |(x, y): Vector| {
+                let [a11, a12, a21, a22] = scale;
+                let (x, y) = (x as f32, y as f32);
+                ((x * a11 + y * a12).round() as i32, (x * a21 + y * a22).round() as i32)
+            };
+            let scaled_offset = (interpreter.scale(x_offset), interpreter.scale(y_offset));
+            let hinted_offset = if flags & ROUND_XY_TO_GRID != 0 { (round_to_pixel(scaled_offset.0), round_to_pixel(scaled_offset.1)) } else { scaled_offset };
+            let offset = |(x, y): Vector, (dx, dy): Vector| (x.wrapping_add(dx), y.wrapping_add(dy));
+
+            let first_point = zone.cur.len();
+            // glyph turns these back into u16 contour ends, same limit as parse_glyph
+            for end in component.contour_ends.iter() {
+                if end + first_point > u16::MAX as usize {
+                    return Err(FontError::Parse(format!("glyph {glyph_index} has too many points in its components")));
+                }
+                zone.contour_ends.push(end + first_point);
+            }
+            for point in 0..outline_len {
+                zone.orus.push(offset(transform(component.orus[point]), (x_offset, y_offset)));
+                zone.org.push(offset(transform(component.org[point]), scaled_offset));
+                zone.cur.push(offset(transform(component.cur[point]), hinted_offset));
+                zone.on_curve.push(component.on_curve[point]);
+                zone.touched.push(0);
+            }
+
+            if flags & MORE_COMPONENTS == 0 {
+                let mut instructions: &[u8] = &[];
+                if flags & WE_HAVE_INSTRUCTIONS != 0 {
+                    let instructions_length = reader.read_u16()? as usize;
+                    instructions = reader.sub_reader(reader.get_location(), instructions_length)?.data();
+                }
+                let phantoms = match component_metrics {
+                    Some(metrics) => metrics,
+                    None => self.phantom_points(glyph_index, bounding_box)?,
+                };
+                for phantom in phantoms {
+                    zone.orus.push(phantom);
+                    zone.org.push((interpreter.scale(phantom.0), interpreter.scale(phantom.1)));
+                    zone.cur.push((interpreter.scale(phantom.0), interpreter.scale(phantom.1)));
+                    zone.on_curve.push(true);
+                    zone.touched.push(0);
+                }
+                return run_glyph_program(interpreter, zone, instructions, budget);
+            }
+        }
+    }
+
+    // in font units: left side bearing point, advance point, vertical origin and vertical advance point.
+    // bounding_box is x_min, y_min, x_max, y_max like in the glyph header
+    fn phantom_points(&self, glyph_index: usize, bounding_box: [i16; 4]) -> Result<Vec<Vector>, FontError> {
+        let mut hhea = self.table(HHEA)?;
+        hhea.go_to(34)?; // numberOfHMetrics
+        let num_long_hor_metrics = hhea.read_u16()? as usize;
+        let mut hmtx = self.table(HMTX)?;
+        if glyph_index < num_long_hor_metrics {
+            hmtx.go_to(glyph_index * 4 + 2)?;
+        } else {
+            hmtx.go_to(num_long_hor_metrics * 4 + (glyph_index - num_long_hor_metrics) * 2)?; // only bearings after the long metrics
+        }
+        let left_side_bearing = hmtx.read_i16()? as i32;
+        let advance = self.glyph_spaces.get(glyph_index).copied().unwrap_or_default() as i32;
+
+        let top = match self.vertical.as_ref().and_then(|metrics| metrics.top_side_bearings.get(glyph_index)) {
+            Some(top_side_bearing) => bounding_box[3] as i32 + *top_side_bearing as i32,
+            None => (self.ascent / self.font_scale).round() as i32,
+        };
+        let left = bounding_box[0] as i32 - left_side_bearing;
+        Ok(vec![(left, 0), (left + advance, 0), (0, top), (0, top - self.vertical_advance(glyph_index) as i32)])
+    }
+}
+
+fn round_to_pixel(value: i32) -> i32 {
+    value.saturating_add(32) & !63
+}
+
+// a simple glyph's points (in font units) ready for the interpreter
+fn outline_zone(interpreter: &Interpreter, points: Vec<(Vector, bool)>, contour_ends: Vec<usize>, phantoms: Vec<Vector>) -> Zone {
+    let mut zone = Zone { contour_ends, ..Default::default() };
+    for (point, on_curve) in points.into_iter().chain(phantoms.into_iter().map(|phantom| (phantom, true))) {
+        let scaled = (interpreter.scale(point.0), interpreter.scale(point.1));
+        zone.orus.push(point);
+        zone.org.push(scaled);
+        zone.cur.push(scaled);
+        zone.on_curve.push(on_curve);
+        zone.touched.push(0);
+    }
+    zone
+}
+
+// the phantom points get rounded before anything else so the advance always ends up on whole pixels,
+// then the glyph's program runs on a copy of the interpreter so nothing it does leaks into other glyphs
+fn run_glyph_program(interpreter: &Interpreter, mut zone: Zone, instructions: &[u8], budget: &mut HintBudget) -> Result<Zone, FontError> {
+    zone.org = zone.cur.clone(); // compound glyphs start from their hinted components
+    let phantoms = zone.cur.len() - PHANTOM_POINTS;
+    zone.cur[phantoms].0 = round_to_pixel(zone.cur[phantoms].0);
+    zone.cur[phantoms + 1].0 = round_to_pixel(zone.cur[phantoms + 1].0);
+    zone.cur[phantoms + 2].1 = round_to_pixel(zone.cur[phantoms + 2].1);
+    zone.cur[phantoms + 3].1 = round_to_pixel(zone.cur[phantoms + 3].1);
+    if instructions.is_empty() {
+        return Ok(zone);
+    }
+    interpreter.clone().run_glyph_program(zone, Arc::from(instructions), &mut budget.instructions)
+}
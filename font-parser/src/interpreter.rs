@@ -0,0 +1,1374 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::font_table_parser::FontError;
+
+// https://learn.microsoft.com/en-us/typography/opentype/spec/tt_instructions
+// https://developer.apple.com/fonts/TrueType-Reference-Manual/RM05/Chap5.html
+// everything in here is 26.6 fixed point pixels unless it says otherwise, vectors are 2.14.
+// where the spec is vague this does what FreeType's v35 interpreter does, that's what fonts get tested against
+
+pub(crate) const TOUCHED_X: u8 = 1;
+pub(crate) const TOUCHED_Y: u8 = 2;
+
+const ONE_2DOT14: i32 = 0x4000;
+const MAX_CALL_DEPTH: usize = 64;
+const MAX_INSTRUCTIONS: usize = 1_000_000; // per program (all of a compound glyph's together), fonts with endless loops just don't get hinted
+const MAX_DISTANCE: i32 = 0x7FFF * 64; // cut ins and the minimum distance, anything past this many pixels means nothing
+const TWILIGHT: usize = 0;
+const GLYPH: usize = 1;
+
+pub(crate) type Vector = (i32, i32);
+
+fn fail(message: impl std::fmt::Display) -> FontError {
+    FontError::Parse(format!("hinting: {message}"))
+}
+
+// a * b / c rounded, in 64 bits so 26.6 * 2.14 doesn't overflow
+fn mul_div(a: i32, b: i32, c: i32) -> i32 {
+    if c == 0 {
+        return 0;
+    }
+    let (product, c) = (a as i64 * b as i64, c as i64);
+    let rounded = if (product < 0) == (c < 0) { (product + c.abs() / 2) / c } else { (product - c.abs() / 2) / c };
+    rounded.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+// value * 2.14 vector component
+fn mul_2dot14(value: i32, component: i32) -> i32 {
+    ((value as i64 * component as i64 + 0x2000) >> 14) as i32
+}
+
+fn normalize(x: i32, y: i32) -> Vector {
+    let length = (x as f64).hypot(y as f64);
+    if length == 0.0 {
+        return (ONE_2DOT14, 0);
+    }
+    ((x as f64 / length * ONE_2DOT14 as f64).round() as i32, (y as f64 / length * ONE_2DOT14 as f64).round() as i32)
+}
+
+// one set of points the instructions work on, the glyph's own (plus its 4 phantom points) or the twilight zone
+#[derive(Clone, Default)]
+pub(crate) struct Zone {
+    pub org: Vec<Vector>, // scaled but not hinted
+    pub cur: Vec<Vector>, // where the instructions moved them
+    pub orus: Vec<Vector>, // unscaled, IUP interpolates with these
+    pub on_curve: Vec<bool>,
+    pub touched: Vec<u8>,
+    pub contour_ends: Vec<usize>, // empty for the twilight zone
+}
+
+impl Zone {
+    pub fn twilight(len: usize) -> Self {
+        Zone {
+            org: vec![(0, 0); len],
+            cur: vec![(0, 0); len],
+            orus: vec![(0, 0); len],
+            on_curve: vec![false; len],
+            touched: vec![0; len],
+            contour_ends: Vec::new(),
+        }
+    }
+
+    // the points the outline is made of, without the phantom points at the end
+    fn outline_len(&self) -> usize {
+        self.contour_ends.last().map_or(0, |end| end + 1)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RoundState {
+    Grid,
+    HalfGrid,
+    DoubleGrid,
+    DownToGrid,
+    UpToGrid,
+    Off,
+    Super { period: i32, phase: i32, threshold: i32 },
+}
+
+// https://learn.microsoft.com/en-us/typography/opentype/spec/tt_graphics_state
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct GraphicsState {
+    auto_flip: bool,
+    control_value_cut_in: i32,
+    delta_base: i32,
+    delta_shift: i32,
+    dual_vector: Vector,
+    freedom_vector: Vector,
+    projection_vector: Vector,
+    instruct_control: i32, // bit 1 turns off glyph programs, bit 2 makes them start from the default state
+    loop_count: i32,
+    min_distance: i32,
+    round_state: RoundState,
+    rp: [usize; 3],
+    single_width_cut_in: i32,
+    single_width_value: i32,
+    zp: [usize; 3],
+}
+
+impl Default for GraphicsState {
+    fn default() -> Self {
+        GraphicsState {
+            auto_flip: true,
+            control_value_cut_in: 68, // 17/16 of a pixel
+            delta_base: 9,
+            delta_shift: 3,
+            dual_vector: (ONE_2DOT14, 0),
+            freedom_vector: (ONE_2DOT14, 0),
+            projection_vector: (ONE_2DOT14, 0),
+            instruct_control: 0,
+            loop_count: 1,
+            min_distance: 64,
+            round_state: RoundState::Grid,
+            rp: [0; 3],
+            single_width_cut_in: 0,
+            single_width_value: 0,
+            zp: [GLYPH; 3],
+        }
+    }
+}
+
+impl GraphicsState {
+    // what every glyph program starts with on top of whatever prep left behind
+    fn reset_for_glyph(&mut self) {
+        self.dual_vector = (ONE_2DOT14, 0);
+        self.freedom_vector = (ONE_2DOT14, 0);
+        self.projection_vector = (ONE_2DOT14, 0);
+        self.loop_count = 1;
+        self.round_state = RoundState::Grid;
+        self.rp = [0; 3];
+        self.zp = [GLYPH; 3];
+    }
+}
+
+// a function (FDEF) or instruction (IDEF) body, code[start..end] without the ENDF
+#[derive(Clone)]
+struct Definition {
+    code: Arc<[u8]>,
+    start: usize,
+    end: usize,
+}
+
+// the interpreter for one ppem. fpgm and prep run on it once, then every glyph gets a copy to run its own program on
+#[derive(Clone)]
+pub(crate) struct Interpreter {
+    ppem: u16,
+    scale: f64, // font units to 26.6
+    cvt: Vec<i32>,
+    storage: Vec<i32>,
+    functions: Vec<Option<Definition>>,
+    instruction_defs: HashMap<u8, Definition>,
+    graphics_state: GraphicsState,
+    default_state: GraphicsState, // after prep, what glyph programs start with
+    twilight: Zone,
+    glyph: Zone,
+    stack: Vec<i32>,
+    max_stack: usize,
+    executed: usize,
+    in_prep: bool,
+}
+
+// the maxp limits the interpreter needs
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct InterpreterLimits {
+    pub max_twilight_points: usize,
+    pub max_storage: usize,
+    pub max_function_defs: usize,
+    pub max_stack_elements: usize,
+}
+
+impl Interpreter {
+    pub fn new(ppem: u16, units_per_em: u16, cvt: &[i16], limits: InterpreterLimits) -> Self {
+        let scale = ppem as f64 * 64.0 / units_per_em.max(1) as f64;
+        Interpreter {
+            ppem,
+            scale,
+            cvt: cvt.iter().map(|value| (*value as f64 * scale).round() as i32).collect(),
+            storage: vec![0; limits.max_storage],
+            functions: vec![None; limits.max_function_defs],
+            instruction_defs: HashMap::new(),
+            graphics_state: GraphicsState::default(),
+            default_state: GraphicsState::default(),
+            twilight: Zone::twilight(limits.max_twilight_points),
+            glyph: Zone::default(),
+            stack: Vec::new(),
+            max_stack: limits.max_stack_elements + 32, // plenty of fonts understate it by a bit
+            executed: 0,
+            in_prep: false,
+        }
+    }
+
+    pub fn scale(&self, font_units: i32) -> i32 {
+        (font_units as f64 * self.scale).round() as i32
+    }
+
+    pub fn run_font_program(&mut self, fpgm: Arc<[u8]>) -> Result<(), FontError> {
+        self.run(fpgm)
+    }
+
+    // prep can change the graphics state for every glyph after it, that's where fonts set their cut ins and such
+    pub fn run_control_value_program(&mut self, prep: Arc<[u8]>) -> Result<(), FontError> {
+        self.in_prep = true;
+        let result = self.run(prep);
+        self.in_prep = false;
+        result?;
+        self.default_state = self.graphics_state;
+        Ok(())
+    }
+
+    // runs a glyph's instructions over it, the hinted points end up in glyph.cur. executed carries the count
+    // from one component of a compound glyph to the next, so they all share one MAX_INSTRUCTIONS
+    pub fn run_glyph_program(&mut self, glyph: Zone, instructions: Arc<[u8]>, executed: &mut usize) -> Result<Zone, FontError> {
+        self.glyph = glyph;
+        if self.default_state.instruct_control & 2 != 0 {
+            self.graphics_state = GraphicsState::default();
+        } else {
+            self.graphics_state = self.default_state;
+        }
+        self.graphics_state.reset_for_glyph();
+        if self.default_state.instruct_control & 1 == 0 {
+            self.executed = *executed;
+            let result = self.execute_all(instructions);
+            *executed = self.executed;
+            result?;
+        }
+        Ok(std::mem::take(&mut self.glyph))
+    }
+
+    fn run(&mut self, code: Arc<[u8]>) -> Result<(), FontError> {
+        self.executed = 0;
+        self.execute_all(code)
+    }
+
+    fn execute_all(&mut self, code: Arc<[u8]>) -> Result<(), FontError> {
+        self.stack.clear();
+        let end = code.len();
+        self.execute(&code, 0, end, 0)
+    }
+
+    fn pop(&mut self) -> Result<i32, FontError> {
+        self.stack.pop().ok_or_else(|| fail("stack underflow"))
+    }
+
+    fn push(&mut self, value: i32) -> Result<(), FontError> {
+        if self.stack.len() >= self.max_stack {
+            return Err(fail("stack overflow"));
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn zone(&self, zp: usize) -> &Zone {
+        if self.graphics_state.zp[zp] == TWILIGHT { &self.twilight } else { &self.glyph }
+    }
+
+    fn zone_mut(&mut self, zp: usize) -> &mut Zone {
+        if self.graphics_state.zp[zp] == TWILIGHT { &mut self.twilight } else { &mut self.glyph }
+    }
+
+    fn point(&self, zp: usize, point: i32) -> Result<usize, FontError> {
+        let zone = self.zone(zp);
+        usize::try_from(point)
+            .ok()
+            .filter(|point| *point < zone.cur.len())
+            .ok_or_else(|| fail(format!("point {point} is out of zone {}", self.graphics_state.zp[zp])))
+    }
+
+    fn cvt_index(&self, index: i32) -> Result<usize, FontError> {
+        usize::try_from(index).ok().filter(|index| *index < self.cvt.len()).ok_or_else(|| fail(format!("cvt entry {index} does not exist")))
+    }
+
+    fn storage_index(&self, index: i32) -> Result<usize, FontError> {
+        usize::try_from(index).ok().filter(|index| *index < self.storage.len()).ok_or_else(|| fail(format!("storage location {index} does not exist")))
+    }
+
+    // a - b along the projection vector
+    fn project(&self, a: Vector, b: Vector) -> i32 {
+        let (x, y) = self.graphics_state.projection_vector;
+        mul_2dot14(a.0.wrapping_sub(b.0), x).wrapping_add(mul_2dot14(a.1.wrapping_sub(b.1), y))
+    }
+
+    // same along the dual projection vector, used on original positions
+    fn dual_project(&self, a: Vector, b: Vector) -> i32 {
+        let (x, y) = self.graphics_state.dual_vector;
+        mul_2dot14(a.0.wrapping_sub(b.0), x).wrapping_add(mul_2dot14(a.1.wrapping_sub(b.1), y))
+    }
+
+    // original distance between a (in zp_a) and b (in zp_b), glyph points measure their unscaled positions like FreeType
+    // does, which matters for composites whose original outline is already the hinted components
+    fn original_distance(&self, zp_a: usize, a: usize, zp_b: usize, b: usize) -> i32 {
+        let (zone_a, zone_b) = (self.zone(zp_a), self.zone(zp_b));
+        if self.graphics_state.zp[zp_a] == TWILIGHT || self.graphics_state.zp[zp_b] == TWILIGHT {
+            self.dual_project(zone_a.org[a], zone_b.org[b])
+        } else {
+            self.scale(self.dual_project(zone_a.orus[a], zone_b.orus[b]))
+        }
+    }
+
+    // how far a point moves along the projection vector for every unit it moves along the freedom vector
+    fn freedom_dot_projection(&self) -> i32 {
+        let (fx, fy) = self.graphics_state.freedom_vector;
+        let (px, py) = self.graphics_state.projection_vector;
+        let dot = ((fx as i64 * px as i64 + fy as i64 * py as i64) >> 14) as i32;
+        if dot.abs() < 0x400 { ONE_2DOT14 } else { dot } // nearly perpendicular vectors would throw points off to infinity
+    }
+
+    // moves a point along the freedom vector so its projection changes by distance
+    fn move_point(&mut self, zp: usize, point: usize, distance: i32, touch: bool) {
+        let (fx, fy) = self.graphics_state.freedom_vector;
+        let dot = self.freedom_dot_projection();
+        let zone = self.zone_mut(zp);
+        if fx != 0 {
+            zone.cur[point].0 = zone.cur[point].0.wrapping_add(mul_div(distance, fx, dot));
+            if touch {
+                zone.touched[point] |= TOUCHED_X;
+            }
+        }
+        if fy != 0 {
+            zone.cur[point].1 = zone.cur[point].1.wrapping_add(mul_div(distance, fy, dot));
+            if touch {
+                zone.touched[point] |= TOUCHED_Y;
+            }
+        }
+    }
+
+    fn move_original(&mut self, zp: usize, point: usize, distance: i32) {
+        let (fx, fy) = self.graphics_state.freedom_vector;
+        let dot = self.freedom_dot_projection();
+        let zone = self.zone_mut(zp);
+        zone.org[point].0 = zone.org[point].0.wrapping_add(mul_div(distance, fx, dot));
+        zone.org[point].1 = zone.org[point].1.wrapping_add(mul_div(distance, fy, dot));
+    }
+
+    // shifts without going through the projection vector, for SHP/SHC/SHZ/SHPIX
+    fn shift_point(&mut self, zp: usize, point: usize, (dx, dy): Vector, touch: bool) {
+        let (fx, fy) = self.graphics_state.freedom_vector;
+        let zone = self.zone_mut(zp);
+        zone.cur[point] = (zone.cur[point].0.wrapping_add(dx), zone.cur[point].1.wrapping_add(dy));
+        if touch {
+            if fx != 0 {
+                zone.touched[point] |= TOUCHED_X;
+            }
+            if fy != 0 {
+                zone.touched[point] |= TOUCHED_Y;
+            }
+        }
+    }
+
+    fn round(&self, distance: i32) -> i32 {
+        // every rounding keeps the sign, a positive distance never rounds to a negative one.
+        // the values come straight from the font, so it all saturates instead of overflowing
+        let sign_kept = |rounded: fn(i32) -> i32| if distance >= 0 { rounded(distance).max(0) } else { rounded(distance.saturating_neg()).max(0).saturating_neg() };
+        match self.graphics_state.round_state {
+            RoundState::Grid => sign_kept(|d| d.saturating_add(32) & !63),
+            RoundState::HalfGrid => sign_kept(|d| (d & !63).saturating_add(32)),
+            RoundState::DoubleGrid => sign_kept(|d| d.saturating_add(16) & !31),
+            RoundState::DownToGrid => sign_kept(|d| d & !63),
+            RoundState::UpToGrid => sign_kept(|d| d.saturating_add(63) & !63),
+            RoundState::Off => distance,
+            RoundState::Super { period, phase, threshold } => {
+                let rounded = |d: i32| d.saturating_sub(phase).saturating_add(threshold).div_euclid(period).saturating_mul(period).saturating_add(phase);
+                if distance >= 0 {
+                    let rounded = rounded(distance);
+                    if rounded < 0 { phase } else { rounded }
+                } else {
+                    let rounded = rounded(distance.saturating_neg()).saturating_neg();
+                    if rounded > 0 { -phase } else { rounded }
+                }
+            }
+        }
+    }
+
+    // SROUND and S45ROUND, grid_period is one pixel or one pixel over sqrt 2
+    fn super_round(selector: i32, grid_period: i32) -> RoundState {
+        let period = match (selector >> 6) & 3 {
+            0 => grid_period / 2,
+            2 => grid_period * 2,
+            _ => grid_period,
+        };
+        let phase = match (selector >> 4) & 3 {
+            0 => 0,
+            1 => period / 4,
+            2 => period / 2,
+            _ => period * 3 / 4,
+        };
+        let threshold = if selector & 15 == 0 { period - 1 } else { ((selector & 15) - 4) * period / 8 };
+        RoundState::Super { period, phase, threshold }
+    }
+
+    // the loop variable only counts for one instruction, after that it's back to 1
+    fn take_loop(&mut self) -> i32 {
+        std::mem::replace(&mut self.graphics_state.loop_count, 1)
+    }
+
+    // counts against MAX_INSTRUCTIONS, loops that run no instructions of their own (LOOPCALL of an empty function,
+    // DELTAs with a huge count) count every round too
+    fn count_instruction(&mut self) -> Result<(), FontError> {
+        self.executed += 1;
+        if self.executed > MAX_INSTRUCTIONS {
+            return Err(fail("too many instructions, the program probably loops forever"));
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, code: &Arc<[u8]>, start: usize, end: usize, depth: usize) -> Result<(), FontError> {
+        if depth > MAX_CALL_DEPTH {
+            return Err(fail("functions call each other too deep"));
+        }
+
+        let mut ip = start;
+        while ip < end {
+            self.count_instruction()?;
+
+            let opcode = code[ip];
+            let mut next = ip + 1;
+            match opcode {
+                // SVTCA, SPVTCA, SFVTCA: the lowest bit is x (1) or y (0)
+                0x00..=0x05 => {
+                    let axis = if opcode & 1 == 1 { (ONE_2DOT14, 0) } else { (0, ONE_2DOT14) };
+                    if opcode < 0x04 {
+                        self.graphics_state.projection_vector = axis;
+                        self.graphics_state.dual_vector = axis;
+                    }
+                    if !matches!(opcode, 0x02 | 0x03) {
+                        self.graphics_state.freedom_vector = axis;
+                    }
+                }
+                // SPVTL, SFVTL: parallel (0) or perpendicular (1) to the line between two points
+                0x06..=0x09 => {
+                    let (p2, p1) = (self.pop()?, self.pop()?);
+                    let (p1, p2) = (self.point(1, p1)?, self.point(2, p2)?);
+                    let (a, b) = (self.zone(1).cur[p1], self.zone(2).cur[p2]);
+                    let vector = self.line_vector(opcode, a, b);
+                    if opcode < 0x08 {
+                        self.graphics_state.projection_vector = vector;
+                        self.graphics_state.dual_vector = vector;
+                    } else {
+                        self.graphics_state.freedom_vector = vector;
+                    }
+                }
+                // SPVFS, SFVFS
+                0x0A | 0x0B => {
+                    let (y, x) = (self.pop()?, self.pop()?);
+                    let vector = normalize(x as i16 as i32, y as i16 as i32);
+                    if opcode == 0x0A {
+                        self.graphics_state.projection_vector = vector;
+                        self.graphics_state.dual_vector = vector;
+                    } else {
+                        self.graphics_state.freedom_vector = vector;
+                    }
+                }
+                // GPV, GFV
+                0x0C | 0x0D => {
+                    let (x, y) = if opcode == 0x0C { self.graphics_state.projection_vector } else { self.graphics_state.freedom_vector };
+                    self.push(x)?;
+                    self.push(y)?;
+                }
+                0x0E => self.graphics_state.freedom_vector = self.graphics_state.projection_vector, // SFVTPV
+                0x0F => self.intersect()?, // ISECT
+                // SRP0, SRP1, SRP2
+                0x10..=0x12 => {
+                    let point = self.pop()?;
+                    self.graphics_state.rp[(opcode - 0x10) as usize] = usize::try_from(point).map_err(|_| fail(format!("point {point} is negative")))?;
+                }
+                // SZP0, SZP1, SZP2, SZPS
+                0x13..=0x16 => {
+                    let zone = self.pop()?;
+                    if zone != 0 && zone != 1 {
+                        return Err(fail(format!("zone {zone} does not exist")));
+                    }
+                    match opcode {
+                        0x16 => self.graphics_state.zp = [zone as usize; 3],
+                        _ => self.graphics_state.zp[(opcode - 0x13) as usize] = zone as usize,
+                    }
+                }
+                0x17 => {
+                    let count = self.pop()?;
+                    if count < 0 {
+                        return Err(fail("negative loop count"));
+                    }
+                    self.graphics_state.loop_count = count.min(0xFFFF);
+                }
+                0x18 => self.graphics_state.round_state = RoundState::Grid, // RTG
+                0x19 => self.graphics_state.round_state = RoundState::HalfGrid, // RTHG
+                0x1A => self.graphics_state.min_distance = self.pop()?.clamp(0, MAX_DISTANCE), // SMD
+                // ELSE, only ever reached at the end of a taken IF branch
+                0x1B => next = skip_branch(code, next, end, false)?,
+                // JMPR, relative to the jump itself
+                0x1C => {
+                    let offset = self.pop()?;
+                    next = jump(ip, offset, end)?;
+                }
+                0x1D => self.graphics_state.control_value_cut_in = self.pop()?.clamp(0, MAX_DISTANCE), // SCVTCI
+                0x1E => self.graphics_state.single_width_cut_in = self.pop()?.clamp(0, MAX_DISTANCE), // SSWCI
+                0x1F => {
+                    let value = self.pop()?.clamp(i16::MIN as i32, i16::MAX as i32);
+                    self.graphics_state.single_width_value = self.scale(value); // SSW, in font units
+                }
+                0x20 => {
+                    let value = *self.stack.last().ok_or_else(|| fail("stack underflow"))?;
+                    self.push(value)?; // DUP
+                }
+                0x21 => {
+                    self.pop()?; // POP
+                }
+                0x22 => self.stack.clear(), // CLEAR
+                0x23 => {
+                    let (b, a) = (self.pop()?, self.pop()?);
+                    self.push(b)?;
+                    self.push(a)?; // SWAP
+                }
+                0x24 => self.push(self.stack.len() as i32)?, // DEPTH
+                // CINDEX, MINDEX: copy or move the k-th element (1 is the top) to the top
+                0x25 | 0x26 => {
+                    let k = self.pop()?;
+                    let index = usize::try_from(k).ok().filter(|k| *k >= 1 && *k <= self.stack.len()).map(|k| self.stack.len() - k).ok_or_else(|| fail(format!("stack index {k} is out of the stack")))?;
+                    let value = if opcode == 0x25 { self.stack[index] } else { self.stack.remove(index) };
+                    self.push(value)?;
+                }
+                // ALIGNPTS
+                0x27 => {
+                    let (p2, p1) = (self.pop()?, self.pop()?);
+                    let (p1, p2) = (self.point(1, p1)?, self.point(0, p2)?);
+                    let distance = self.project(self.zone(0).cur[p2], self.zone(1).cur[p1]) / 2;
+                    self.move_point(1, p1, distance, true);
+                    self.move_point(0, p2, -distance, true);
+                }
+                // UTP
+                0x29 => {
+                    let point = self.pop()?;
+                    let point = self.point(0, point)?;
+                    let (fx, fy) = self.graphics_state.freedom_vector;
+                    let zone = self.zone_mut(0);
+                    if fx != 0 {
+                        zone.touched[point] &= !TOUCHED_X;
+                    }
+                    if fy != 0 {
+                        zone.touched[point] &= !TOUCHED_Y;
+                    }
+                }
+                // LOOPCALL, CALL
+                0x2A | 0x2B => {
+                    let function = self.pop()?;
+                    let count = if opcode == 0x2A { self.pop()? } else { 1 };
+                    let definition = usize::try_from(function)
+                        .ok()
+                        .and_then(|function| self.functions.get(function).cloned().flatten())
+                        .ok_or_else(|| fail(format!("function {function} is not defined")))?;
+                    for _ in 0..count.max(0) {
+                        self.count_instruction()?;
+                        self.execute(&definition.code, definition.start, definition.end, depth + 1)?;
+                    }
+                }
+                // FDEF
+                0x2C => {
+                    let function = self.pop()?;
+                    let body_end = find_endf(code, next, end)?;
+                    let slot = usize::try_from(function)
+                        .ok()
+                        .and_then(|function| self.functions.get_mut(function))
+                        .ok_or_else(|| fail(format!("function {function} is more than maxp allows")))?;
+                    *slot = Some(Definition { code: code.clone(), start: next, end: body_end });
+                    next = body_end + 1;
+                }
+                0x2D => return Ok(()), // ENDF
+                // MDAP[r]
+                0x2E | 0x2F => {
+                    let point = self.pop()?;
+                    let point = self.point(0, point)?;
+                    let distance = if opcode == 0x2F {
+                        let current = self.project(self.zone(0).cur[point], (0, 0));
+                        self.round(current).wrapping_sub(current)
+                    } else {
+                        0
+                    };
+                    self.move_point(0, point, distance, true);
+                    self.graphics_state.rp[0] = point;
+                    self.graphics_state.rp[1] = point;
+                }
+                // IUP[y], IUP[x]
+                0x30 | 0x31 => self.interpolate_untouched(opcode == 0x31),
+                // SHP[a]
+                0x32 | 0x33 => {
+                    let (shift, _) = self.reference_shift(opcode)?;
+                    for _ in 0..self.take_loop() {
+                        let point = self.pop()?;
+                        let point = self.point(2, point)?;
+                        self.shift_point(2, point, shift, true);
+                    }
+                }
+                // SHC[a]
+                0x34 | 0x35 => {
+                    let contour = self.pop()?;
+                    let (shift, reference) = self.reference_shift(opcode)?;
+                    let zone = self.zone(2);
+                    let contour = usize::try_from(contour).ok().filter(|contour| *contour < zone.contour_ends.len()).ok_or_else(|| fail(format!("contour {contour} does not exist")))?;
+                    let first = if contour == 0 { 0 } else { zone.contour_ends[contour - 1] + 1 };
+                    for point in first..=zone.contour_ends[contour] {
+                        if Some(point) != reference {
+                            self.shift_point(2, point, shift, true);
+                        }
+                    }
+                }
+                // SHZ[a], moves the whole zone but doesn't touch anything. FreeType goes by zp2 whatever zone gets popped
+                0x36 | 0x37 => {
+                    let zone = self.pop()?;
+                    if zone != 0 && zone != 1 {
+                        return Err(fail(format!("zone {zone} does not exist")));
+                    }
+                    let (shift, reference) = self.reference_shift(opcode)?;
+                    let limit = if self.graphics_state.zp[2] == TWILIGHT { self.twilight.cur.len() } else { self.glyph.outline_len() };
+                    for point in 0..limit {
+                        if Some(point) != reference {
+                            self.shift_point(2, point, shift, false);
+                        }
+                    }
+                }
+                // SHPIX
+                0x38 => {
+                    let distance = self.pop()?;
+                    let (fx, fy) = self.graphics_state.freedom_vector;
+                    let shift = (mul_2dot14(distance, fx), mul_2dot14(distance, fy));
+                    for _ in 0..self.take_loop() {
+                        let point = self.pop()?;
+                        let point = self.point(2, point)?;
+                        self.shift_point(2, point, shift, true);
+                    }
+                }
+                0x39 => self.interpolate_points()?, // IP
+                // MSIRP[a]
+                0x3A | 0x3B => {
+                    let (distance, point) = (self.pop()?, self.pop()?);
+                    let point = self.point(1, point)?;
+                    let rp0 = self.point(0, self.graphics_state.rp[0] as i32)?;
+                    if self.graphics_state.zp[1] == TWILIGHT {
+                        let origin = self.zone(0).org[rp0];
+                        self.zone_mut(1).org[point] = origin;
+                        self.move_original(1, point, distance);
+                        let zone = self.zone_mut(1);
+                        zone.cur[point] = zone.org[point];
+                    }
+                    let current = self.project(self.zone(1).cur[point], self.zone(0).cur[rp0]);
+                    self.move_point(1, point, distance.wrapping_sub(current), true);
+                    self.graphics_state.rp[1] = self.graphics_state.rp[0];
+                    self.graphics_state.rp[2] = point;
+                    if opcode == 0x3B {
+                        self.graphics_state.rp[0] = point;
+                    }
+                }
+                // ALIGNRP
+                0x3C => {
+                    let rp0 = self.point(0, self.graphics_state.rp[0] as i32)?;
+                    for _ in 0..self.take_loop() {
+                        let point = self.pop()?;
+                        let point = self.point(1, point)?;
+                        let distance = self.project(self.zone(1).cur[point], self.zone(0).cur[rp0]);
+                        self.move_point(1, point, distance.wrapping_neg(), true);
+                    }
+                }
+                0x3D => self.graphics_state.round_state = RoundState::DoubleGrid, // RTDG
+                // MIAP[r]
+                0x3E | 0x3F => {
+                    let (cvt, point) = (self.pop()?, self.pop()?);
+                    let (cvt, point) = (self.cvt_index(cvt)?, self.point(0, point)?);
+                    let mut distance = self.cvt[cvt];
+                    if self.graphics_state.zp[0] == TWILIGHT {
+                        let (fx, fy) = self.graphics_state.freedom_vector;
+                        let position = (mul_2dot14(distance, fx), mul_2dot14(distance, fy));
+                        self.twilight.org[point] = position;
+                        self.twilight.cur[point] = position;
+                    }
+                    let current = self.project(self.zone(0).cur[point], (0, 0));
+                    if opcode == 0x3F {
+                        if distance.abs_diff(current) > self.graphics_state.control_value_cut_in as u32 {
+                            distance = current;
+                        }
+                        distance = self.round(distance);
+                    }
+                    self.move_point(0, point, distance.wrapping_sub(current), true);
+                    self.graphics_state.rp[0] = point;
+                    self.graphics_state.rp[1] = point;
+                }
+                // NPUSHB, NPUSHW
+                0x40 | 0x41 => {
+                    let count = *code.get(next).ok_or_else(|| fail("NPUSH runs past the end of the program"))? as usize;
+                    next = self.push_inline(code, next + 1, count, opcode == 0x41, end)?;
+                }
+                // WS
+                0x42 => {
+                    let (value, index) = (self.pop()?, self.pop()?);
+                    let index = self.storage_index(index)?;
+                    self.storage[index] = value;
+                }
+                // RS
+                0x43 => {
+                    let index = self.pop()?;
+                    let index = self.storage_index(index)?;
+                    self.push(self.storage[index])?;
+                }
+                // WCVTP, WCVTF
+                0x44 | 0x70 => {
+                    let (value, index) = (self.pop()?, self.pop()?);
+                    let index = self.cvt_index(index)?;
+                    self.cvt[index] = if opcode == 0x70 { self.scale(value) } else { value };
+                }
+                // RCVT
+                0x45 => {
+                    let index = self.pop()?;
+                    let index = self.cvt_index(index)?;
+                    self.push(self.cvt[index])?;
+                }
+                // GC[a], current (0) or original (1) position
+                0x46 | 0x47 => {
+                    let point = self.pop()?;
+                    let point = self.point(2, point)?;
+                    let value = if opcode == 0x47 { self.dual_project(self.zone(2).org[point], (0, 0)) } else { self.project(self.zone(2).cur[point], (0, 0)) };
+                    self.push(value)?;
+                }
+                // SCFS
+                0x48 => {
+                    let (value, point) = (self.pop()?, self.pop()?);
+                    let point = self.point(2, point)?;
+                    let current = self.project(self.zone(2).cur[point], (0, 0));
+                    self.move_point(2, point, value.wrapping_sub(current), true);
+                    if self.graphics_state.zp[2] == TWILIGHT {
+                        self.twilight.org[point] = self.twilight.cur[point];
+                    }
+                }
+                // MD[a], current (0x49) or original (0x4A) outline, p1 (zp0) - p2 (zp1)
+                0x49 | 0x4A => {
+                    let (p2, p1) = (self.pop()?, self.pop()?);
+                    let (p1, p2) = (self.point(0, p1)?, self.point(1, p2)?);
+                    let distance = if opcode == 0x49 { self.project(self.zone(0).cur[p1], self.zone(1).cur[p2]) } else { self.original_distance(0, p1, 1, p2) };
+                    self.push(distance)?;
+                }
+                0x4B | 0x4C => self.push(self.ppem as i32)?, // MPPEM, MPS (FreeType answers the ppem for both)
+                0x4D => self.graphics_state.auto_flip = true, // FLIPON
+                0x4E => self.graphics_state.auto_flip = false, // FLIPOFF
+                0x4F => {
+                    self.pop()?; // DEBUG
+                }
+                // LT, LTEQ, GT, GTEQ, EQ, NEQ, AND, OR
+                0x50..=0x55 | 0x5A | 0x5B => {
+                    let (b, a) = (self.pop()?, self.pop()?);
+                    let result = match opcode {
+                        0x50 => a < b,
+                        0x51 => a <= b,
+                        0x52 => a > b,
+                        0x53 => a >= b,
+                        0x54 => a == b,
+                        0x55 => a != b,
+                        0x5A => a != 0 && b != 0,
+                        _ => a != 0 || b != 0,
+                    };
+                    self.push(result as i32)?;
+                }
+                // ODD, EVEN, after rounding
+                0x56 | 0x57 => {
+                    let value = self.pop()?;
+                    let odd = self.round(value) & 127 == 64;
+                    self.push((odd == (opcode == 0x56)) as i32)?;
+                }
+                // IF
+                0x58 => {
+                    if self.pop()? == 0 {
+                        next = skip_branch(code, next, end, true)?;
+                    }
+                }
+                0x59 => (), // EIF
+                0x5C => {
+                    let value = self.pop()?;
+                    self.push((value == 0) as i32)?; // NOT
+                }
+                // DELTAP1, DELTAP2, DELTAP3
+                0x5D | 0x71 | 0x72 => {
+                    let count = self.pop()?;
+                    let base = match opcode {
+                        0x5D => 0,
+                        0x71 => 16,
+                        _ => 32,
+                    };
+                    for _ in 0..count.max(0) {
+                        self.count_instruction()?;
+                        let (point, argument) = (self.pop()?, self.pop()?);
+                        let Ok(point) = self.point(0, point) else { continue }; // FreeType skips bad points here
+                        if let Some(distance) = self.delta_distance(argument, base) {
+                            self.move_point(0, point, distance, true);
+                        }
+                    }
+                }
+                0x5E => self.graphics_state.delta_base = self.pop()?.clamp(0, u16::MAX as i32), // SDB
+                0x5F => self.graphics_state.delta_shift = self.pop()?.clamp(0, 6), // SDS
+                // ADD, SUB, DIV, MUL
+                0x60..=0x63 => {
+                    let (b, a) = (self.pop()?, self.pop()?);
+                    let result = match opcode {
+                        0x60 => a.wrapping_add(b),
+                        0x61 => a.wrapping_sub(b),
+                        0x62 => {
+                            if b == 0 {
+                                return Err(fail("division by zero"));
+                            }
+                            (a as i64 * 64 / b as i64) as i32
+                        }
+                        _ => mul_div(a, b, 64),
+                    };
+                    self.push(result)?;
+                }
+                // ABS, NEG, FLOOR, CEILING
+                0x64..=0x67 => {
+                    let value = self.pop()?;
+                    let result = match opcode {
+                        0x64 => value.wrapping_abs(),
+                        0x65 => value.wrapping_neg(),
+                        0x66 => value & !63,
+                        _ => value.wrapping_add(63) & !63,
+                    };
+                    self.push(result)?;
+                }
+                // ROUND[ab], the engine compensation is always 0 here
+                0x68..=0x6B => {
+                    let value = self.pop()?;
+                    self.push(self.round(value))?;
+                }
+                0x6C..=0x6F => (), // NROUND[ab], leaves the value as is without compensation
+                // DELTAC1, DELTAC2, DELTAC3
+                0x73..=0x75 => {
+                    let count = self.pop()?;
+                    let base = (opcode - 0x73) as i32 * 16;
+                    for _ in 0..count.max(0) {
+                        self.count_instruction()?;
+                        let (index, argument) = (self.pop()?, self.pop()?);
+                        let index = self.cvt_index(index)?;
+                        if let Some(distance) = self.delta_distance(argument, base) {
+                            self.cvt[index] = self.cvt[index].wrapping_add(distance);
+                        }
+                    }
+                }
+                0x76 => {
+                    let selector = self.pop()?;
+                    self.graphics_state.round_state = Self::super_round(selector, 64); // SROUND
+                }
+                0x77 => {
+                    let selector = self.pop()?;
+                    self.graphics_state.round_state = Self::super_round(selector, 45); // S45ROUND
+                }
+                // JROT, JROF
+                0x78 | 0x79 => {
+                    let (condition, offset) = (self.pop()?, self.pop()?);
+                    if (condition != 0) == (opcode == 0x78) {
+                        next = jump(ip, offset, end)?;
+                    }
+                }
+                0x7A => self.graphics_state.round_state = RoundState::Off, // ROFF
+                0x7C => self.graphics_state.round_state = RoundState::UpToGrid, // RUTG
+                0x7D => self.graphics_state.round_state = RoundState::DownToGrid, // RDTG
+                // SANGW, AA, both obsolete
+                0x7E | 0x7F => {
+                    self.pop()?;
+                }
+                // FLIPPT
+                0x80 => {
+                    for _ in 0..self.take_loop() {
+                        let point = self.pop()?;
+                        let point = self.point(0, point)?;
+                        let zone = self.zone_mut(0);
+                        zone.on_curve[point] = !zone.on_curve[point];
+                    }
+                }
+                // FLIPRGON, FLIPRGOFF
+                0x81 | 0x82 => {
+                    let (high, low) = (self.pop()?, self.pop()?);
+                    let (low, high) = (self.point(0, low)?, self.point(0, high)?);
+                    let zone = self.zone_mut(0);
+                    for point in low..=high {
+                        zone.on_curve[point] = opcode == 0x81;
+                    }
+                }
+                // SCANCTRL, SCANTYPE, dropout control doesn't matter for outlines
+                0x85 | 0x8D => {
+                    self.pop()?;
+                }
+                // SDPVTL
+                0x86 | 0x87 => {
+                    let (p2, p1) = (self.pop()?, self.pop()?);
+                    let (p1, p2) = (self.point(1, p1)?, self.point(2, p2)?);
+                    self.graphics_state.dual_vector = self.line_vector(opcode, self.zone(1).org[p1], self.zone(2).org[p2]);
+                    self.graphics_state.projection_vector = self.line_vector(opcode, self.zone(1).cur[p1], self.zone(2).cur[p2]);
+                }
+                // GETINFO, only the version. 35 is what FreeType's v35 interpreter says, no ClearType or grayscale
+                0x88 => {
+                    let selector = self.pop()?;
+                    self.push(if selector & 1 != 0 { 35 } else { 0 })?;
+                }
+                // IDEF
+                0x89 => {
+                    let instruction = self.pop()?;
+                    let body_end = find_endf(code, next, end)?;
+                    self.instruction_defs.insert(instruction as u8, Definition { code: code.clone(), start: next, end: body_end });
+                    next = body_end + 1;
+                }
+                // ROLL
+                0x8A => {
+                    let (c, b, a) = (self.pop()?, self.pop()?, self.pop()?);
+                    self.push(b)?;
+                    self.push(c)?;
+                    self.push(a)?;
+                }
+                // MAX, MIN
+                0x8B | 0x8C => {
+                    let (b, a) = (self.pop()?, self.pop()?);
+                    self.push(if opcode == 0x8B { a.max(b) } else { a.min(b) })?;
+                }
+                // INSTCTRL, only prep is allowed to use it
+                0x8E => {
+                    let (selector, value) = (self.pop()?, self.pop()?);
+                    if self.in_prep && (1..=3).contains(&selector) {
+                        let bit = 1 << (selector - 1);
+                        let state = &mut self.graphics_state;
+                        state.instruct_control = (state.instruct_control & !bit) | if value != 0 { bit } else { 0 };
+                    }
+                }
+                // PUSHB, PUSHW
+                0xB0..=0xBF => {
+                    let count = (opcode & 7) as usize + 1;
+                    next = self.push_inline(code, next, count, opcode >= 0xB8, end)?;
+                }
+                // MDRP[abcde]
+                0xC0..=0xDF => self.move_direct_relative(opcode)?,
+                // MIRP[abcde]
+                0xE0..=0xFF => self.move_indirect_relative(opcode)?,
+                _ => {
+                    let Some(definition) = self.instruction_defs.get(&opcode).cloned() else {
+                        return Err(fail(format!("unknown instruction 0x{opcode:02X}")));
+                    };
+                    self.execute(&definition.code, definition.start, definition.end, depth + 1)?;
+                }
+            }
+            ip = next;
+        }
+        Ok(())
+    }
+
+    // pushes count bytes or words that follow in the code, returns where the next instruction starts
+    fn push_inline(&mut self, code: &[u8], start: usize, count: usize, words: bool, end: usize) -> Result<usize, FontError> {
+        let size = if words { 2 } else { 1 };
+        let next = start + count * size;
+        if next > end {
+            return Err(fail("push runs past the end of the program"));
+        }
+        for i in 0..count {
+            let value = if words { i16::from_be_bytes([code[start + i * 2], code[start + i * 2 + 1]]) as i32 } else { code[start + i] as i32 };
+            self.push(value)?;
+        }
+        Ok(next)
+    }
+
+    // from b to a, turned a quarter counterclockwise for the odd opcodes
+    fn line_vector(&self, opcode: u8, a: Vector, b: Vector) -> Vector {
+        let (mut x, mut y) = (a.0.wrapping_sub(b.0), a.1.wrapping_sub(b.1));
+        if opcode & 1 == 1 {
+            (x, y) = (y.wrapping_neg(), x);
+        }
+        normalize(x, y)
+    }
+
+    // the distance a DELTA argument moves by, if it applies to the current ppem
+    fn delta_distance(&self, argument: i32, base: i32) -> Option<i32> {
+        let ppem = ((argument & 0xF0) >> 4) + base + self.graphics_state.delta_base;
+        if ppem != self.ppem as i32 {
+            return None;
+        }
+        let mut steps = (argument & 0xF) - 8; // -8..=7 without a 0
+        if steps >= 0 {
+            steps += 1;
+        }
+        Some(steps * (1 << (6 - self.graphics_state.delta_shift)))
+    }
+
+    // how far rp1 (zp0) or rp2 (zp1) has moved so far along the freedom vector, what SHP, SHC and SHZ shift by.
+    // the reference point comes back too when it's in zp2, so SHC and SHZ can leave it where it is
+    fn reference_shift(&self, opcode: u8) -> Result<(Vector, Option<usize>), FontError> {
+        let (zp, reference) = if opcode & 1 == 1 { (0, self.graphics_state.rp[1]) } else { (1, self.graphics_state.rp[2]) };
+        let reference = self.point(zp, reference as i32)?;
+        let zone = self.zone(zp);
+        let distance = self.project(zone.cur[reference], zone.org[reference]);
+        let (fx, fy) = self.graphics_state.freedom_vector;
+        let dot = self.freedom_dot_projection();
+        let same_zone = self.graphics_state.zp[zp] == self.graphics_state.zp[2];
+        Ok(((mul_div(distance, fx, dot), mul_div(distance, fy, dot)), same_zone.then_some(reference)))
+    }
+
+    // ISECT, moves a point to where lines a0-a1 (zp1) and b0-b1 (zp0) cross
+    fn intersect(&mut self) -> Result<(), FontError> {
+        let (b1, b0, a1, a0, point) = (self.pop()?, self.pop()?, self.pop()?, self.pop()?, self.pop()?);
+        let (b0, b1) = (self.point(0, b0)?, self.point(0, b1)?);
+        let (a0, a1) = (self.point(1, a0)?, self.point(1, a1)?);
+        let point = self.point(2, point)?;
+        let (b0, b1) = (self.zone(0).cur[b0], self.zone(0).cur[b1]);
+        let (a0, a1) = (self.zone(1).cur[a0], self.zone(1).cur[a1]);
+
+        let (dbx, dby) = (b1.0.wrapping_sub(b0.0), b1.1.wrapping_sub(b0.1));
+        let (dax, day) = (a1.0.wrapping_sub(a0.0), a1.1.wrapping_sub(a0.1));
+        let (dx, dy) = (b0.0.wrapping_sub(a0.0), b0.1.wrapping_sub(a0.1));
+        let discriminant = mul_div(dax, dby.wrapping_neg(), 64).wrapping_add(mul_div(day, dbx, 64));
+        let dot = mul_div(dax, dbx, 64).wrapping_add(mul_div(day, dby, 64));
+        // lines that meet at less than about 3 degrees are treated as parallel
+        let position = if 19 * (discriminant as i64).abs() > (dot as i64).abs() {
+            let value = mul_div(dx, dby.wrapping_neg(), 64).wrapping_add(mul_div(dy, dbx, 64));
+            (a0.0.wrapping_add(mul_div(value, dax, discriminant)), a0.1.wrapping_add(mul_div(value, day, discriminant)))
+        } else {
+            let average = |a: i32, b: i32, c: i32, d: i32| ((a as i64 + b as i64 + c as i64 + d as i64) / 4) as i32;
+            (average(a0.0, a1.0, b0.0, b1.0), average(a0.1, a1.1, b0.1, b1.1))
+        };
+        let zone = self.zone_mut(2);
+        zone.cur[point] = position;
+        zone.touched[point] |= TOUCHED_X | TOUCHED_Y;
+        Ok(())
+    }
+
+    // IP, keeps points (zp2) where they were relative to rp1 (zp0) and rp2 (zp1) in the original outline
+    fn interpolate_points(&mut self) -> Result<(), FontError> {
+        let rp1 = self.point(0, self.graphics_state.rp[1] as i32)?;
+        let rp2 = self.point(1, self.graphics_state.rp[2] as i32)?;
+        let current_base = self.zone(0).cur[rp1];
+        let original_range = self.original_distance(1, rp2, 0, rp1);
+        let current_range = self.project(self.zone(1).cur[rp2], current_base);
+
+        for _ in 0..self.take_loop() {
+            let point = self.pop()?;
+            let point = self.point(2, point)?;
+            let original = self.original_distance(2, point, 0, rp1);
+            let current = self.project(self.zone(2).cur[point], current_base);
+            let new = if original == 0 {
+                0
+            } else if original_range != 0 {
+                mul_div(original, current_range, original_range)
+            } else {
+                current
+            };
+            self.move_point(2, point, new.wrapping_sub(current), true);
+        }
+        Ok(())
+    }
+
+    // IUP, points the instructions didn't touch follow the touched ones around them on their contour
+    fn interpolate_untouched(&mut self, x_axis: bool) {
+        let (flag, axis): (u8, fn(&mut Vector) -> &mut i32) = if x_axis { (TOUCHED_X, |v| &mut v.0) } else { (TOUCHED_Y, |v| &mut v.1) };
+        let zone = &mut self.glyph;
+        let mut contour_start = 0;
+        for contour_end in zone.contour_ends.clone() {
+            // contours without a touched point stay where they are, with one they all shift along with it
+            let touched: Vec<usize> = (contour_start..=contour_end).filter(|point| zone.touched[*point] & flag != 0).collect();
+            for (i, point) in touched.iter().copied().enumerate() {
+                // the untouched points between this touched one and the next, wrapping around the contour
+                let next = touched[(i + 1) % touched.len()];
+                let mut between = Vec::new();
+                let mut p = if point == contour_end { contour_start } else { point + 1 };
+                while p != next {
+                    between.push(p);
+                    p = if p == contour_end { contour_start } else { p + 1 };
+                }
+                interpolate_run(zone, axis, &between, point, next);
+            }
+            contour_start = contour_end + 1;
+        }
+    }
+
+    // MDRP, moves a point (zp1) to keep its original distance from rp0 (zp0), rounded and kept above the minimum
+    fn move_direct_relative(&mut self, opcode: u8) -> Result<(), FontError> {
+        let point = self.pop()?;
+        let point = self.point(1, point)?;
+        let rp0 = self.point(0, self.graphics_state.rp[0] as i32)?;
+        let state = self.graphics_state;
+
+        let mut original = self.original_distance(1, point, 0, rp0);
+        if original.abs_diff(state.single_width_value) < state.single_width_cut_in as u32 {
+            original = if original >= 0 { state.single_width_value } else { state.single_width_value.wrapping_neg() };
+        }
+        let mut distance = if opcode & 4 != 0 { self.round(original) } else { original };
+        if opcode & 8 != 0 {
+            distance = keep_min_distance(distance, original, state.min_distance);
+        }
+
+        let current = self.project(self.zone(1).cur[point], self.zone(0).cur[rp0]);
+        self.move_point(1, point, distance.wrapping_sub(current), true);
+        self.graphics_state.rp[1] = rp0;
+        self.graphics_state.rp[2] = point;
+        if opcode & 16 != 0 {
+            self.graphics_state.rp[0] = point;
+        }
+        Ok(())
+    }
+
+    // MIRP, same but the distance comes from the cvt when it's close enough to the original one
+    fn move_indirect_relative(&mut self, opcode: u8) -> Result<(), FontError> {
+        let (cvt, point) = (self.pop()?, self.pop()?);
+        let point = self.point(1, point)?;
+        let rp0 = self.point(0, self.graphics_state.rp[0] as i32)?;
+        let mut cvt_distance = if cvt == -1 { 0 } else { self.cvt[self.cvt_index(cvt)?] };
+        let state = self.graphics_state;
+
+        if cvt_distance.abs_diff(state.single_width_value) < state.single_width_cut_in as u32 {
+            cvt_distance = if cvt_distance >= 0 { state.single_width_value } else { state.single_width_value.wrapping_neg() };
+        }
+        // twilight points get placed at the cvt distance from rp0 first
+        if state.zp[1] == TWILIGHT {
+            let (fx, fy) = state.freedom_vector;
+            let origin = self.zone(0).org[rp0];
+            let position = (origin.0.wrapping_add(mul_2dot14(cvt_distance, fx)), origin.1.wrapping_add(mul_2dot14(cvt_distance, fy)));
+            self.twilight.org[point] = position;
+            self.twilight.cur[point] = position;
+        }
+
+        let original = self.dual_project(self.zone(1).org[point], self.zone(0).org[rp0]);
+        let current = self.project(self.zone(1).cur[point], self.zone(0).cur[rp0]);
+        if state.auto_flip && (original ^ cvt_distance) < 0 {
+            cvt_distance = cvt_distance.wrapping_neg();
+        }
+
+        let mut distance = if opcode & 4 != 0 {
+            if state.zp[0] == state.zp[1] && cvt_distance.abs_diff(original) > state.control_value_cut_in as u32 {
+                cvt_distance = original;
+            }
+            self.round(cvt_distance)
+        } else {
+            cvt_distance
+        };
+        if opcode & 8 != 0 {
+            distance = keep_min_distance(distance, original, state.min_distance);
+        }
+
+        self.move_point(1, point, distance.wrapping_sub(current), true);
+        self.graphics_state.rp[1] = rp0;
+        self.graphics_state.rp[2] = point;
+        if opcode & 16 != 0 {
+            self.graphics_state.rp[0] = point;
+        }
+        Ok(())
+    }
+}
+
+fn keep_min_distance(distance: i32, original: i32, min_distance: i32) -> i32 {
+    if original >= 0 { distance.max(min_distance) } else { distance.min(-min_distance) } // min_distance is never negative
+}
+
+// moves the untouched points between touched points from and to like the stretch between those two.
+// points outside of their original span shift along with the nearer one
+fn interpolate_run(zone: &mut Zone, axis: fn(&mut Vector) -> &mut i32, points: &[usize], from: usize, to: usize) {
+    if points.is_empty() {
+        return;
+    }
+    let (mut ref1, mut ref2) = (from, to);
+    if *axis(&mut zone.orus[ref1]) > *axis(&mut zone.orus[ref2]) {
+        (ref1, ref2) = (ref2, ref1);
+    }
+    let (orus1, orus2) = (*axis(&mut zone.orus[ref1]), *axis(&mut zone.orus[ref2]));
+    let (org1, org2) = (*axis(&mut zone.org[ref1]), *axis(&mut zone.org[ref2]));
+    let (cur1, cur2) = (*axis(&mut zone.cur[ref1]), *axis(&mut zone.cur[ref2]));
+    let (delta1, delta2) = (cur1.wrapping_sub(org1), cur2.wrapping_sub(org2));
+
+    for point in points.iter().copied() {
+        let original = *axis(&mut zone.org[point]);
+        let moved = if original <= org1 {
+            original.wrapping_add(delta1)
+        } else if original >= org2 {
+            original.wrapping_add(delta2)
+        } else if cur1 == cur2 || orus1 == orus2 {
+            cur1
+        } else {
+            let unscaled = *axis(&mut zone.orus[point]);
+            cur1.wrapping_add(mul_div(unscaled.wrapping_sub(orus1), cur2.wrapping_sub(cur1), orus2.wrapping_sub(orus1)))
+        };
+        *axis(&mut zone.cur[point]) = moved;
+    }
+}
+
+// how many bytes an instruction takes up, pushes carry their values inline
+fn instruction_length(code: &[u8], ip: usize) -> Result<usize, FontError> {
+    let opcode = code[ip];
+    let length = match opcode {
+        0x40 => 2 + *code.get(ip + 1).ok_or_else(|| fail("NPUSHB runs past the end of the program"))? as usize,
+        0x41 => 2 + *code.get(ip + 1).ok_or_else(|| fail("NPUSHW runs past the end of the program"))? as usize * 2,
+        0xB0..=0xB7 => 1 + (opcode - 0xB0) as usize + 1,
+        0xB8..=0xBF => 1 + ((opcode - 0xB8) as usize + 1) * 2,
+        _ => 1,
+    };
+    Ok(length)
+}
+
+// from right after an IF (or ELSE) to right after its matching ELSE (when stop_at_else) or EIF
+fn skip_branch(code: &[u8], mut ip: usize, end: usize, stop_at_else: bool) -> Result<usize, FontError> {
+    let mut nesting = 0;
+    while ip < end {
+        let opcode = code[ip];
+        ip += instruction_length(code, ip)?;
+        match opcode {
+            0x58 => nesting += 1,
+            0x1B if nesting == 0 && stop_at_else => return Ok(ip),
+            0x59 if nesting == 0 => return Ok(ip),
+            0x59 => nesting -= 1,
+            _ => (),
+        }
+    }
+    Err(fail("IF without an EIF"))
+}
+
+// where the ENDF of a definition starting at ip is
+fn find_endf(code: &[u8], mut ip: usize, end: usize) -> Result<usize, FontError> {
+    while ip < end {
+        if code[ip] == 0x2D {
+            return Ok(ip);
+        }
+        ip += instruction_length(code, ip)?;
+    }
+    Err(fail("FDEF without an ENDF"))
+}
+
+fn jump(ip: usize, offset: i32, end: usize) -> Result<usize, FontError> {
+    usize::try_from(ip as i64 + offset as i64)
+        .ok()
+        .filter(|target| *target <= end && offset != 0)
+        .ok_or_else(|| fail(format!("jump by {offset} leaves the program")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // at 10 ppem and 640 units per em one font unit is one 26.6 unit, so the numbers below read the same in both
+    fn interpreter(cvt: &[i16]) -> Interpreter {
+        let limits = InterpreterLimits { max_twilight_points: 4, max_storage: 4, max_function_defs: 4, max_stack_elements: 32 };
+        Interpreter::new(10, 640, cvt, limits)
+    }
+
+    // one contour with its points along the x axis
+    fn zone(xs: &[i32]) -> Zone {
+        let points: Vec<Vector> = xs.iter().map(|x| (*x, 0)).collect();
+        Zone {
+            org: points.clone(),
+            cur: points.clone(),
+            orus: points,
+            on_curve: vec![true; xs.len()],
+            touched: vec![0; xs.len()],
+            contour_ends: vec![xs.len() - 1],
+        }
+    }
+
+    fn run(code: &[u8]) -> Result<Vec<i32>, FontError> {
+        let mut interpreter = interpreter(&[]);
+        interpreter.run_font_program(Arc::from(code))?;
+        Ok(interpreter.stack)
+    }
+
+    fn hint(cvt: &[i16], xs: &[i32], code: &[u8]) -> Result<Vec<i32>, FontError> {
+        let zone = interpreter(cvt).run_glyph_program(zone(xs), Arc::from(code), &mut 0)?;
+        Ok(zone.cur.iter().map(|point| point.0).collect())
+    }
+
+    // PUSHW 0x7FFF twice, MUL, DUP, MUL: i32::MAX once mul_div clamps it
+    const HUGE: [u8; 8] = [0xB9, 0x7F, 0xFF, 0x7F, 0xFF, 0x63, 0x20, 0x63];
+
+    #[test]
+    fn pushes_stay_inside_the_program() {
+        assert_eq!(run(&[0xB1, 1, 2, 0xB8, 0xFF, 0xFE]).unwrap(), [1, 2, -2]);
+        assert_eq!(run(&[0x40, 3, 7, 8, 9, 0x41, 1, 0x80, 0x00]).unwrap(), [7, 8, 9, -32768]);
+        assert!(run(&[0xB2, 1, 2]).is_err());
+        assert!(run(&[0x40, 4, 1, 2]).is_err());
+        assert!(run(&[0x41, 1, 0x01]).is_err());
+        assert!(run(&[0x40]).is_err());
+    }
+
+    #[test]
+    fn jumps_and_branches_skip_what_they_should() {
+        // JMPR by 3 from itself lands past the PUSHB 9
+        assert_eq!(run(&[0xB0, 3, 0x1C, 0xB0, 9, 0xB0, 7]).unwrap(), [7]);
+        // IF 0 takes the ELSE, IF 1 skips it, nested IFs in the skipped branch don't end it early
+        assert_eq!(run(&[0xB0, 0, 0x58, 0xB0, 1, 0x1B, 0xB0, 2, 0x59]).unwrap(), [2]);
+        assert_eq!(run(&[0xB0, 1, 0x58, 0xB0, 1, 0x1B, 0xB0, 2, 0x59]).unwrap(), [1]);
+        assert_eq!(run(&[0xB0, 0, 0x58, 0xB0, 1, 0x58, 0xB0, 3, 0x59, 0x1B, 0xB0, 2, 0x59]).unwrap(), [2]);
+        assert!(run(&[0xB0, 0, 0x58, 0xB0, 1]).is_err());
+        assert!(run(&[0xB0, 100, 0x1C]).is_err());
+    }
+
+    #[test]
+    fn functions_run_where_they_are_called() {
+        let define = [0xB0, 1, 0x2C, 0xB0, 5, 0x2D];
+        assert_eq!(run(&[&define[..], &[0xB0, 1, 0x2B]].concat()).unwrap(), [5]);
+        assert_eq!(run(&[&define[..], &[0xB1, 3, 1, 0x2A]].concat()).unwrap(), [5, 5, 5]);
+        assert!(run(&[0xB0, 2, 0x2B]).is_err());
+        assert!(run(&[0xB0, 9, 0x2C, 0x2D]).is_err());
+        // a function calling itself stops at the call depth limit
+        assert!(run(&[0xB0, 0, 0x2C, 0xB0, 0, 0x2B, 0x2D, 0xB0, 0, 0x2B]).is_err());
+    }
+
+    #[test]
+    fn mirp_uses_the_cvt_within_the_cut_in() {
+        // rp0 is point 0, point 1 is 170 (2.66 pixels) away from it
+        let cvt = [130, 300];
+        // 130 is within 68 of 170, so it's the cvt value rounded
+        assert_eq!(hint(&cvt, &[0, 170], &[0xB0, 0, 0x10, 0xB1, 1, 0, 0xE4]).unwrap(), [0, 128]);
+        // 300 isn't, so it's the original distance rounded
+        assert_eq!(hint(&cvt, &[0, 170], &[0xB0, 0, 0x10, 0xB1, 1, 1, 0xE4]).unwrap(), [0, 192]);
+        // without rounding there's no cut in either
+        assert_eq!(hint(&cvt, &[0, 170], &[0xB0, 0, 0x10, 0xB1, 1, 1, 0xE0]).unwrap(), [0, 300]);
+    }
+
+    #[test]
+    fn iup_follows_the_touched_points() {
+        // SCFS moves points 0 and 2 to 10 and 120, 1 is halfway between them and 3 is past the end so it shifts with 2
+        let code = [0xB1, 0, 10, 0x48, 0xB1, 2, 120, 0x48, 0x31];
+        assert_eq!(hint(&[], &[0, 50, 100, 150], &code).unwrap(), [10, 65, 120, 170]);
+        // no touched points, nothing moves
+        assert_eq!(hint(&[], &[0, 50, 100], &[0x31]).unwrap(), [0, 50, 100]);
+    }
+
+    #[test]
+    fn deltap_only_moves_at_its_ppem() {
+        // the delta base is 9, so 0x18 is 10 ppem one step (an eighth of a pixel) right and 0x28 is 11 ppem
+        assert_eq!(hint(&[], &[0, 50], &[0xB4, 0x18, 0, 0x28, 1, 2, 0x5D]).unwrap(), [8, 50]);
+        assert_eq!(hint(&[], &[0, 50], &[0xB2, 0x17, 0, 1, 0x5D]).unwrap(), [-8, 50]);
+        assert_eq!(hint(&[], &[0, 50], &[0xB2, 0x38, 0, 1, 0x5D]).unwrap(), [0, 50]);
+    }
+
+    #[test]
+    fn font_values_dont_overflow() {
+        let huge = |rest: &[u8]| [&HUGE[..], rest].concat();
+        let min = huge(&[0x65, 0xB0, 1, 0x61]); // NEG, PUSHB 1, SUB
+        let programs = [
+            [&HUGE[..], &[0x68]].concat(), // ROUND
+            [&min[..], &[0x68]].concat(),
+            [&HUGE[..], &[0x5E, 0xB2, 0x18, 0, 1, 0x5D]].concat(), // SDB, DELTAP1
+            [&[0xB0, 0][..], &min, &[0x44, 0xB1, 0, 0, 0x3F]].concat(), // WCVTP, MIAP
+            [&[0xB0, 0][..], &HUGE, &[0x44, 0xB0, 0], &HUGE, &[0x1D, 0x3F]].concat(), // WCVTP, SCVTCI, MIAP
+            [&HUGE[..], &[0x1E], &HUGE, &[0x1F, 0xB0, 1, 0xC4]].concat(), // SSWCI, SSW, MDRP
+            [&[0xB0, 0][..], &min, &[0x44, 0xB0, 0], &HUGE, &[0x1E, 0xB1, 1, 0, 0xE4]].concat(), // WCVTP, SSWCI, MIRP
+            [&[0xB0, 0][..], &min, &[0x48, 0xB0, 1], &HUGE, &[0x48, 0xB5, 2, 0, 1, 2, 3, 0x0F]].concat(), // SCFS, ISECT
+            [&[0xB0, 0][..], &min, &[0x48, 0xB0, 2], &HUGE, &[0x48, 0x31]].concat(), // SCFS, IUP
+            [&[0xB0, 0][..], &min, &[0x48, 0xB0, 0, 0x10, 0xB0, 1, 0x3C]].concat(), // SCFS, ALIGNRP
+        ];
+        for program in programs {
+            let _ = hint(&[0], &[0, 50, 100, 150], &program);
+        }
+    }
+
+    #[test]
+    fn loops_count_against_the_instruction_limit() {
+        // LOOPCALL of an empty function i32::MAX times
+        assert!(run(&[&[0xB0, 0, 0x2C, 0x2D][..], &HUGE, &[0xB0, 0, 0x2A]].concat()).is_err());
+        // DELTAC1 with i32::MAX pairs runs out of stack long before that, but it has to stop either way
+        assert!(hint(&[0], &[0, 50], &[&HUGE[..], &[0x73]].concat()).is_err());
+    }
+
+    #[test]
+    fn components_share_the_instruction_limit() {
+        // PUSHB 1, POP: two instructions, counted on top of what the earlier components ran
+        let code: Arc<[u8]> = Arc::from(&[0xB0, 1, 0x21][..]);
+        let mut executed = 10;
+        assert!(interpreter(&[]).run_glyph_program(zone(&[0]), code.clone(), &mut executed).is_ok());
+        assert_eq!(executed, 12);
+        let mut executed = MAX_INSTRUCTIONS - 1;
+        assert!(interpreter(&[]).run_glyph_program(zone(&[0]), code, &mut executed).is_err());
+    }
+}
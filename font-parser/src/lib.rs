@@ -0,0 +1,18 @@
+// TrueType parsing on its own, no bevy in here so tools and other renderers can use it
+pub mod bitmap;
+pub mod color;
+pub mod decoration;
+pub mod font_reader;
+pub mod font_table_parser;
+pub mod geometry;
+pub mod glyph_cache;
+pub mod hinting;
+mod interpreter;
+pub mod lint;
+pub mod name;
+pub mod os2;
+pub mod paint;
+pub mod post;
+pub mod svg;
+pub mod validate;
+pub mod vertical;
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::font_table_parser::{setup_implied_points, FontData, FontError, Glyph};
+use crate::geometry::Point;
+
+const EPSILON: f32 = 0.01; // in scaled units, FONT_SIZE_CONSTANT per em
+const CURVE_STEPS: usize = 4; // segments per quadratic when looking for self intersections
+
+// outlines that parse fine but probably aren't what the designer meant (or that we parse wrong)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LintKind {
+    SinglePointContour,
+    DegenerateContour, // fewer than 3 points or no area
+    WrongDirection, // outer contours go clockwise, holes counterclockwise
+    OutsideBoundingBox,
+    DuplicatePoint,
+    SelfIntersection,
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            LintKind::SinglePointContour => "single point contour",
+            LintKind::DegenerateContour => "degenerate contour",
+            LintKind::WrongDirection => "contour goes the wrong way (or overlaps another one)",
+            LintKind::OutsideBoundingBox => "point outside the bounding box",
+            LintKind::DuplicatePoint => "duplicate consecutive point",
+            LintKind::SelfIntersection => "contour crosses itself",
+        };
+        write!(f, "{text}")
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GlyphLint {
+    pub kind: LintKind,
+    pub contour: usize,
+    pub point: Point, // where to look, same units as Glyph coordinates
+}
+
+impl fmt::Display for GlyphLint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "contour {}: {} at ({:.2}, {:.2})", self.contour, self.kind, self.point.x, self.point.y)
+    }
+}
+
+// lints for every glyph that has any, what --lint prints
+#[derive(Default)]
+pub struct LintReport {
+    pub glyphs: Vec<(usize, Vec<char>, Vec<GlyphLint>)>, // (glyph index, characters mapped to it, lints)
+    pub unparsed: Vec<(usize, String)>, // glyphs that didnt parse at all
+}
+
+impl fmt::Display for LintReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut counts: HashMap<LintKind, usize> = HashMap::new();
+        for (glyph_index, chars, lints) in self.glyphs.iter() {
+            let chars: Vec<String> = chars.iter().map(|c| format!("U+{:04X} '{c}'", *c as u32)).collect();
+            writeln!(f, "glyph {glyph_index} {}", chars.join(" "))?;
+            for lint in lints {
+                *counts.entry(lint.kind).or_default() += 1;
+                writeln!(f, "    {lint}")?;
+            }
+        }
+        for (glyph_index, err) in self.unparsed.iter() {
+            writeln!(f, "glyph {glyph_index} could not be parsed: {err}")?;
+        }
+
+        let mut counts: Vec<_> = counts.into_iter().map(|(kind, count)| format!("{count} {kind}")).collect();
+        counts.sort();
+        write!(f, "{} glyphs with lints ({})", self.glyphs.len(), if counts.is_empty() { "none".to_string() } else { counts.join(", ") })
+    }
+}
+
+impl FontData {
+    // parses the glyph again from scratch, the cached one has already dropped the raw points
+    pub fn lint_glyph(&self, glyph_index: usize) -> Result<Vec<GlyphLint>, FontError> {
+        Ok(lint_outline(&self.parse_glyph(glyph_index, 0, &mut 0)?))
+    }
+
+    pub fn lint_report(&self) -> LintReport {
+        let mut chars_of: HashMap<usize, Vec<char>> = HashMap::new();
+        for (unicode, glyph_index) in self.unicodes_to_index.iter() {
+            chars_of.entry(*glyph_index).or_default().extend(char::from_u32(*unicode));
+        }
+
+        let mut report = LintReport::default();
+        for glyph_index in 0..self.num_glyphs {
+            match self.lint_glyph(glyph_index) {
+                Ok(lints) if lints.is_empty() => (),
+                Ok(lints) => {
+                    let mut chars = chars_of.remove(&glyph_index).unwrap_or_default();
+                    chars.sort();
+                    report.glyphs.push((glyph_index, chars, lints));
+                }
+                Err(err) => report.unparsed.push((glyph_index, err.to_string())),
+            }
+        }
+        report
+    }
+}
+
+// needs the raw outline (coordinates + contour_end_pts), before setup_implied_points clears them
+pub fn lint_outline(glyph: &Glyph) -> Vec<GlyphLint> {
+    let mut lints = Vec::new();
+    let [x_min, y_min, x_max, y_max] = glyph.bounding_box;
+
+    let mut contours: Vec<&[(Point, bool)]> = Vec::with_capacity(glyph.contour_end_pts.len());
+    let mut contour_start = 0;
+    for end in glyph.contour_end_pts.iter() {
+        let end = (*end as usize + 1).min(glyph.coordinates.len());
+        contours.push(glyph.coordinates.get(contour_start..end).unwrap_or(&[]));
+        contour_start = end.max(contour_start);
+    }
+
+    let areas: Vec<f32> = contours.iter().map(|contour| signed_area(contour)).collect();
+    for (index, contour) in contours.iter().enumerate() {
+        let first = contour.first().map_or(Point::ZERO, |point| point.0);
+        match contour.len() {
+            0 => continue,
+            1 => {
+                lints.push(GlyphLint { kind: LintKind::SinglePointContour, contour: index, point: first });
+                continue;
+            }
+            _ if contour.len() < 3 || areas[index].abs() < EPSILON => {
+                lints.push(GlyphLint { kind: LintKind::DegenerateContour, contour: index, point: first });
+                continue;
+            }
+            _ => (),
+        }
+
+        for (i, (point, _)) in contour.iter().enumerate() {
+            if point.x < x_min - EPSILON || point.x > x_max + EPSILON || point.y < y_min - EPSILON || point.y > y_max + EPSILON {
+                lints.push(GlyphLint { kind: LintKind::OutsideBoundingBox, contour: index, point: *point });
+            }
+            let next = contour[(i + 1) % contour.len()].0;
+            if (next - *point).x.abs() < EPSILON && (next - *point).y.abs() < EPSILON {
+                lints.push(GlyphLint { kind: LintKind::DuplicatePoint, contour: index, point: *point });
+            }
+        }
+
+        // a contour inside an odd number of others is a hole, y goes up so clockwise is a negative area.
+        // only fully contained ones count, contours that just overlap (common in variable fonts) can go either way
+        let depth = contours
+            .iter()
+            .enumerate()
+            .filter(|(other, other_contour)| {
+                *other != index && areas[*other].abs() > areas[index].abs() && contour.iter().all(|(point, _)| contains(other_contour, *point))
+            })
+            .count();
+        let clockwise = areas[index] < 0.0;
+        if clockwise != depth.is_multiple_of(2) {
+            lints.push(GlyphLint { kind: LintKind::WrongDirection, contour: index, point: first });
+        }
+    }
+
+    let mut with_implied = glyph.clone();
+    setup_implied_points(std::slice::from_mut(&mut with_implied));
+    for (index, contour) in with_implied.contour_coordinates.iter().enumerate() {
+        if contours.get(index).is_some_and(|raw| raw.len() >= 3) {
+            lints.extend(self_intersections(&flatten(contour)).map(|point| GlyphLint { kind: LintKind::SelfIntersection, contour: index, point }));
+        }
+    }
+    lints
+}
+
+// shoelace formula over the on and off curve points, close enough to tell the direction
+fn signed_area(contour: &[(Point, bool)]) -> f32 {
+    let area: f32 = (0..contour.len())
+        .map(|i| {
+            let (a, b) = (contour[i].0, contour[(i + 1) % contour.len()].0);
+            a.x * b.y - b.x * a.y
+        })
+        .sum();
+    area * 0.5
+}
+
+// even-odd ray cast to the right
+fn contains(contour: &[(Point, bool)], point: Point) -> bool {
+    let mut inside = false;
+    for i in 0..contour.len() {
+        let (a, b) = (contour[i].0, contour[(i + 1) % contour.len()].0);
+        if (a.y > point.y) != (b.y > point.y) && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+// same walk render_text does, a quadratic every two points
+pub(crate) fn flatten(contour: &[(Point, u8)]) -> Vec<Point> {
+    let length = contour.len();
+    let mut points = Vec::with_capacity(length * CURVE_STEPS / 2 + 1);
+    let mut i = 0;
+    while i < length {
+        let (a, b, c) = (contour[i].0, contour[(i + 1) % length].0, contour[(i + 2) % length].0);
+        for step in 0..CURVE_STEPS {
+            let t = step as f32 / CURVE_STEPS as f32;
+            points.push(a * ((1.0 - t) * (1.0 - t)) + b * (2.0 * (1.0 - t) * t) + c * (t * t));
+        }
+        i += 2;
+    }
+    points
+}
+
+fn cross(a: Point, b: Point) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+// crossings between segments of the closed polyline that arent neighbours
+fn self_intersections(polyline: &[Point]) -> impl Iterator<Item = Point> + '_ {
+    let n = polyline.len();
+    (0..n).flat_map(move |i| {
+        ((i + 2)..n).filter_map(move |j| {
+            if i == 0 && j == n - 1 {
+                return None; // they share the closing point
+            }
+            let (p, r) = (polyline[i], polyline[(i + 1) % n] - polyline[i]);
+            let (q, s) = (polyline[j], polyline[(j + 1) % n] - polyline[j]);
+            let denominator = cross(r, s);
+            if denominator.abs() < f32::EPSILON {
+                return None;
+            }
+            let t = cross(q - p, s) / denominator;
+            let u = cross(q - p, r) / denominator;
+            ((0.0..1.0).contains(&t) && (0.0..1.0).contains(&u) && t > 1e-4 && u > 1e-4).then(|| p + r * t)
+        })
+    })
+}
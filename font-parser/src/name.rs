@@ -0,0 +1,241 @@
+use std::fmt;
+
+use crate::font_reader::{FontReader, Tag};
+use crate::font_table_parser::{FontData, FontError};
+
+pub const NAME: Tag = Tag::new(b"name");
+
+// https://learn.microsoft.com/en-us/typography/opentype/spec/name#name-ids
+pub const COPYRIGHT: u16 = 0;
+pub const FAMILY: u16 = 1;
+pub const SUBFAMILY: u16 = 2;
+pub const FULL_NAME: u16 = 4;
+pub const VERSION: u16 = 5;
+pub const MANUFACTURER: u16 = 8;
+pub const DESIGNER: u16 = 9;
+pub const LICENSE: u16 = 13;
+pub const LICENSE_URL: u16 = 14;
+pub const TYPOGRAPHIC_FAMILY: u16 = 16;
+pub const TYPOGRAPHIC_SUBFAMILY: u16 = 17;
+
+const UNICODE: u16 = 0;
+const MACINTOSH: u16 = 1;
+const WINDOWS: u16 = 3;
+
+// the upper half of Mac Roman, the lower half is ascii
+const MAC_ROMAN: &str = "ÄÅÇÉÑÖÜáàâäãåçéèêëíìîïñóòôöõúùûü†°¢£§•¶ß®©™´¨≠ÆØ∞±≤≥¥µ∂∑∏π∫ªºΩæø¿¡¬√ƒ≈∆«»…\u{A0}ÀÃÕŒœ–—“”‘’÷◊ÿŸ⁄€‹›ﬁﬂ‡·‚„‰ÂÊÁËÈÍÎÏÌÓÔ\u{F8FF}ÒÚÛÙıˆ˜¯˘˙˚¸˝˛ˇ";
+
+#[derive(Clone, Debug)]
+pub struct NameRecord {
+    pub platform_id: u16,
+    pub encoding_id: u16,
+    pub language_id: u16,
+    pub name_id: u16,
+    pub language: Option<String>, // BCP 47 tag when we know it, e.g. "en-US"
+    pub value: String,
+}
+
+// the names people usually want to see, in one language
+#[derive(Clone, Debug, Default)]
+pub struct FontMetadata {
+    pub family: Option<String>,
+    pub subfamily: Option<String>,
+    pub full_name: Option<String>,
+    pub version: Option<String>,
+    pub designer: Option<String>,
+    pub manufacturer: Option<String>,
+    pub copyright: Option<String>,
+    pub license: Option<String>,
+    pub license_url: Option<String>,
+}
+
+impl fmt::Display for FontMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = [
+            ("family", &self.family),
+            ("style", &self.subfamily),
+            ("full name", &self.full_name),
+            ("version", &self.version),
+            ("designer", &self.designer),
+            ("manufacturer", &self.manufacturer),
+            ("copyright", &self.copyright),
+            ("license", &self.license),
+            ("license url", &self.license_url),
+        ];
+        let mut first = true;
+        for (label, value) in fields.iter() {
+            let Some(value) = value else { continue };
+            if !first {
+                writeln!(f)?;
+            }
+            write!(f, "{label}: {value}")?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+impl FontData {
+    // https://learn.microsoft.com/en-us/typography/opentype/spec/name
+    // records we cant decode (other platforms/encodings) are left out
+    pub fn name_records(&self) -> Result<Vec<NameRecord>, FontError> {
+        let name = self.table(NAME)?;
+        let mut reader = name;
+        let format = reader.read_u16()?;
+        let count = reader.read_u16()? as usize;
+        let storage = name.reader_from(reader.read_offset16()?.to_usize())?;
+
+        let mut raw_records = Vec::with_capacity(count);
+        for _ in 0..count {
+            let (platform_id, encoding_id, language_id, name_id) = (reader.read_u16()?, reader.read_u16()?, reader.read_u16()?, reader.read_u16()?);
+            let (length, offset) = (reader.read_u16()? as usize, reader.read_offset16()?.to_usize());
+            raw_records.push((platform_id, encoding_id, language_id, name_id, length, offset));
+        }
+
+        // format 1 adds language tags, language ids from 0x8000 point into them
+        let mut language_tags = Vec::new();
+        if format == 1 {
+            for _ in 0..reader.read_u16()? {
+                let (length, offset) = (reader.read_u16()? as usize, reader.read_offset16()?.to_usize());
+                language_tags.push(String::from_utf16_lossy(&utf16_units(storage.sub_reader(offset, length)?)));
+            }
+        }
+
+        let mut records = Vec::with_capacity(count);
+        for (platform_id, encoding_id, language_id, name_id, length, offset) in raw_records {
+            let Ok(bytes) = storage.sub_reader(offset, length) else { continue };
+            let value = match (platform_id, encoding_id) {
+                (UNICODE, _) | (WINDOWS, 0 | 1 | 10) => String::from_utf16_lossy(&utf16_units(bytes)),
+                (MACINTOSH, 0) => bytes.data().iter().map(|byte| mac_roman(*byte)).collect(),
+                _ => continue,
+            };
+            let language = match platform_id {
+                _ if language_id >= 0x8000 => language_tags.get(language_id as usize - 0x8000).cloned(),
+                WINDOWS => windows_language(language_id).map(str::to_string),
+                MACINTOSH => mac_language(language_id).map(str::to_string),
+                _ => None,
+            };
+            records.push(NameRecord { platform_id, encoding_id, language_id, name_id, language, value });
+        }
+        Ok(records)
+    }
+
+    // one name in the closest language we have: an exact tag match, then the same language ("en" for "en-GB"),
+    // then english, then whatever comes first. windows records win over mac ones when both match
+    pub fn name(&self, name_id: u16, language: &str) -> Option<String> {
+        let records = self.name_records().ok()?;
+        pick_name(&records, name_id, language)
+    }
+
+    // an empty FontMetadata when the font has no (readable) name table
+    pub fn metadata(&self, language: &str) -> FontMetadata {
+        let records = self.name_records().unwrap_or_default();
+        let name = |name_id| pick_name(&records, name_id, language);
+        FontMetadata {
+            family: name(TYPOGRAPHIC_FAMILY).or_else(|| name(FAMILY)),
+            subfamily: name(TYPOGRAPHIC_SUBFAMILY).or_else(|| name(SUBFAMILY)),
+            full_name: name(FULL_NAME),
+            version: name(VERSION),
+            designer: name(DESIGNER),
+            manufacturer: name(MANUFACTURER),
+            copyright: name(COPYRIGHT),
+            license: name(LICENSE),
+            license_url: name(LICENSE_URL),
+        }
+    }
+}
+
+fn pick_name(records: &[NameRecord], name_id: u16, language: &str) -> Option<String> {
+    let primary = |tag: &str| tag.split('-').next().unwrap_or(tag).to_ascii_lowercase();
+    let wanted = primary(language);
+    records
+        .iter()
+        .filter(|record| record.name_id == name_id && !record.value.is_empty())
+        .min_by_key(|record| {
+            let tag = record.language.as_deref().unwrap_or("");
+            let rank = if tag.eq_ignore_ascii_case(language) {
+                0
+            } else if primary(tag) == wanted {
+                1
+            } else if primary(tag) == "en" {
+                2
+            } else {
+                3
+            };
+            (rank, record.platform_id != WINDOWS)
+        })
+        .map(|record| record.value.clone())
+}
+
+fn utf16_units(reader: FontReader) -> Vec<u16> {
+    reader.data().chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect()
+}
+
+fn mac_roman(byte: u8) -> char {
+    match byte {
+        0..0x80 => byte as char,
+        _ => MAC_ROMAN.chars().nth(byte as usize - 0x80).unwrap_or(char::REPLACEMENT_CHARACTER),
+    }
+}
+
+// the common ones, https://learn.microsoft.com/en-us/typography/opentype/spec/name#windows-language-ids
+fn windows_language(language_id: u16) -> Option<&'static str> {
+    Some(match language_id {
+        0x0409 => "en-US",
+        0x0809 => "en-GB",
+        0x0407 => "de-DE",
+        0x040C => "fr-FR",
+        0x0410 => "it-IT",
+        0x0C0A => "es-ES",
+        0x0416 => "pt-BR",
+        0x0816 => "pt-PT",
+        0x0413 => "nl-NL",
+        0x041D => "sv-SE",
+        0x0406 => "da-DK",
+        0x0414 => "nb-NO",
+        0x040B => "fi-FI",
+        0x0415 => "pl-PL",
+        0x0405 => "cs-CZ",
+        0x040E => "hu-HU",
+        0x0419 => "ru-RU",
+        0x0422 => "uk-UA",
+        0x0408 => "el-GR",
+        0x041F => "tr-TR",
+        0x040D => "he-IL",
+        0x0401 => "ar-SA",
+        0x0439 => "hi-IN",
+        0x041E => "th-TH",
+        0x042A => "vi-VN",
+        0x0411 => "ja-JP",
+        0x0412 => "ko-KR",
+        0x0804 => "zh-CN",
+        0x0404 => "zh-TW",
+        0x0C04 => "zh-HK",
+        _ => return None,
+    })
+}
+
+// https://developer.apple.com/fonts/TrueType-Reference-Manual/RM06/Chap6name.html
+fn mac_language(language_id: u16) -> Option<&'static str> {
+    Some(match language_id {
+        0 => "en",
+        1 => "fr",
+        2 => "de",
+        3 => "it",
+        4 => "nl",
+        5 => "sv",
+        6 => "es",
+        7 => "da",
+        8 => "pt",
+        9 => "no",
+        10 => "he",
+        11 => "ja",
+        12 => "ar",
+        13 => "fi",
+        14 => "el",
+        19 => "zh-Hant",
+        23 => "ko",
+        33 => "zh-Hans",
+        _ => return None,
+    })
+}
@@ -0,0 +1,144 @@
+use crate::font_reader::{FontReader, Tag};
+use crate::font_table_parser::{FontData, FontError};
+
+pub const OS2: Tag = Tag::new(b"OS/2");
+
+// fsSelection bits
+pub const ITALIC: u16 = 1 << 0;
+pub const BOLD: u16 = 1 << 5;
+pub const REGULAR: u16 = 1 << 6;
+pub const USE_TYPO_METRICS: u16 = 1 << 7;
+pub const OBLIQUE: u16 = 1 << 9;
+
+// sub/superscript boxes, in font units
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ScriptMetrics {
+    pub x_size: i16,
+    pub y_size: i16,
+    pub x_offset: i16,
+    pub y_offset: i16,
+}
+
+// https://learn.microsoft.com/en-us/typography/opentype/spec/os2
+// everything is in font units, multiply by FontData::font_scale to get to the same units as the outlines.
+// fields that only exist in later versions are None on older tables
+#[derive(Clone, Debug, Default)]
+pub struct Os2 {
+    pub version: u16,
+    pub x_avg_char_width: i16,
+    pub weight_class: u16, // 100 thin .. 400 regular .. 700 bold .. 900 black
+    pub width_class: u16, // 1 ultra condensed .. 5 normal .. 9 ultra expanded
+    pub fs_type: u16, // embedding permissions
+    pub subscript: ScriptMetrics,
+    pub superscript: ScriptMetrics,
+    pub strikeout_size: i16,
+    pub strikeout_position: i16,
+    pub family_class: i16,
+    pub panose: [u8; 10],
+    pub unicode_ranges: [u32; 4], // 128 bits, see supports_unicode_range
+    pub vendor_id: Tag,
+    pub fs_selection: u16,
+    pub first_char_index: u16,
+    pub last_char_index: u16,
+    pub typo_ascender: Option<i16>, // these five aren't in the 68 byte tables of old Apple fonts
+    pub typo_descender: Option<i16>,
+    pub typo_line_gap: Option<i16>,
+    pub win_ascent: Option<u16>,
+    pub win_descent: Option<u16>, // positive, below the baseline
+    pub code_page_ranges: Option<[u32; 2]>, // version 1+
+    pub x_height: Option<i16>, // version 2+
+    pub cap_height: Option<i16>,
+    pub default_char: Option<u16>,
+    pub break_char: Option<u16>,
+    pub max_context: Option<u16>,
+}
+
+fn read_script_metrics(reader: &mut FontReader) -> std::io::Result<ScriptMetrics> {
+    Ok(ScriptMetrics { x_size: reader.read_i16()?, y_size: reader.read_i16()?, x_offset: reader.read_i16()?, y_offset: reader.read_i16()? })
+}
+
+impl Os2 {
+    pub fn parse(mut reader: FontReader) -> std::io::Result<Self> {
+        let version = reader.read_u16()?;
+        let mut os2 = Os2 {
+            version,
+            x_avg_char_width: reader.read_i16()?,
+            weight_class: reader.read_u16()?,
+            width_class: reader.read_u16()?,
+            fs_type: reader.read_u16()?,
+            subscript: read_script_metrics(&mut reader)?,
+            superscript: read_script_metrics(&mut reader)?,
+            strikeout_size: reader.read_i16()?,
+            strikeout_position: reader.read_i16()?,
+            family_class: reader.read_i16()?,
+            panose: reader.read_bytes()?,
+            unicode_ranges: [reader.read_u32()?, reader.read_u32()?, reader.read_u32()?, reader.read_u32()?],
+            vendor_id: reader.read_tag()?,
+            fs_selection: reader.read_u16()?,
+            first_char_index: reader.read_u16()?,
+            last_char_index: reader.read_u16()?,
+            ..Default::default()
+        };
+
+        // plenty of fonts claim a newer version than the table is long, so stop at whatever runs out
+        os2.typo_ascender = reader.read_i16().ok();
+        os2.typo_descender = reader.read_i16().ok();
+        os2.typo_line_gap = reader.read_i16().ok();
+        os2.win_ascent = reader.read_u16().ok();
+        os2.win_descent = reader.read_u16().ok();
+        if version >= 1 {
+            let Ok(ranges) = reader.read_u32().and_then(|first| Ok([first, reader.read_u32()?])) else { return Ok(os2) };
+            os2.code_page_ranges = Some(ranges);
+        }
+        if version >= 2 {
+            os2.x_height = reader.read_i16().ok();
+            os2.cap_height = reader.read_i16().ok();
+            os2.default_char = reader.read_u16().ok();
+            os2.break_char = reader.read_u16().ok();
+            os2.max_context = reader.read_u16().ok();
+        }
+        Ok(os2)
+    }
+
+    // ascender, descender and line gap, None when the table stops before them
+    pub fn typo_metrics(&self) -> Option<(i16, i16, i16)> {
+        Some((self.typo_ascender?, self.typo_descender?, self.typo_line_gap?))
+    }
+
+    pub fn use_typo_metrics(&self) -> bool {
+        self.fs_selection & USE_TYPO_METRICS != 0
+    }
+
+    pub fn is_italic(&self) -> bool {
+        self.fs_selection & (ITALIC | OBLIQUE) != 0
+    }
+
+    pub fn is_bold(&self) -> bool {
+        self.fs_selection & BOLD != 0
+    }
+
+    pub fn is_regular(&self) -> bool {
+        self.fs_selection & REGULAR != 0
+    }
+
+    // bit numbers from https://learn.microsoft.com/en-us/typography/opentype/spec/os2#ur
+    pub fn supports_unicode_range(&self, bit: u32) -> bool {
+        bit < 128 && self.unicode_ranges[bit as usize / 32] & (1 << (bit % 32)) != 0
+    }
+}
+
+impl FontData {
+    // os2 stays None when the font has no OS/2 table (old mac fonts), errors when it's cut short before the 68 bytes every version has.
+    // run after get_glyph_spacings, fonts that set USE_TYPO_METRICS get their line metrics from here instead of hhea
+    pub fn get_os2(&mut self) -> Result<(), FontError> {
+        if !self.font_table.contains_key(&OS2) {
+            return Ok(());
+        }
+        let os2 = Os2::parse(self.table(OS2)?)?;
+        if os2.use_typo_metrics() && let Some((ascender, descender, line_gap)) = os2.typo_metrics() {
+            self.set_line_metrics(ascender as f32, descender as f32, line_gap as f32);
+        }
+        self.os2 = Some(os2);
+        Ok(())
+    }
+}
@@ -0,0 +1,436 @@
+use std::cell::Cell;
+
+use crate::color::{PaletteColor, COLR, FOREGROUND};
+use crate::font_reader::FontReader;
+use crate::font_table_parser::{FontData, FontError, Glyph};
+use crate::geometry::Point;
+
+const MAX_PAINT_DEPTH: usize = 64; // the graph is supposed to be acyclic, this stops the ones that aren't
+const MAX_PAINT_NODES: usize = 100_000; // paints reused all over the graph (a composite whose source and backdrop are the same) get read every time
+
+// 2x3 affine, x' = xx * x + xy * y + dx and y' = yx * x + yy * y + dy.
+// translations are scaled like the outlines
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Affine {
+    pub xx: f32,
+    pub yx: f32,
+    pub xy: f32,
+    pub yy: f32,
+    pub dx: f32,
+    pub dy: f32,
+}
+
+impl Default for Affine {
+    fn default() -> Self {
+        Affine::IDENTITY
+    }
+}
+
+impl Affine {
+    pub const IDENTITY: Affine = Affine { xx: 1.0, yx: 0.0, xy: 0.0, yy: 1.0, dx: 0.0, dy: 0.0 };
+
+    pub fn translate(dx: f32, dy: f32) -> Self {
+        Affine { dx, dy, ..Affine::IDENTITY }
+    }
+
+    pub fn scale(x: f32, y: f32) -> Self {
+        Affine { xx: x, yy: y, ..Affine::IDENTITY }
+    }
+
+    // counterclockwise
+    pub fn rotate(degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Affine { xx: cos, yx: sin, xy: -sin, yy: cos, dx: 0.0, dy: 0.0 }
+    }
+
+    pub fn skew(x_degrees: f32, y_degrees: f32) -> Self {
+        Affine { xy: -x_degrees.to_radians().tan(), yx: y_degrees.to_radians().tan(), ..Affine::IDENTITY }
+    }
+
+    // self applied after other
+    pub fn then(&self, other: &Affine) -> Affine {
+        Affine {
+            xx: self.xx * other.xx + self.xy * other.yx,
+            yx: self.yx * other.xx + self.yy * other.yx,
+            xy: self.xx * other.xy + self.xy * other.yy,
+            yy: self.yx * other.xy + self.yy * other.yy,
+            dx: self.xx * other.dx + self.xy * other.dy + self.dx,
+            dy: self.yx * other.dx + self.yy * other.dy + self.dy,
+        }
+    }
+
+    fn around(&self, center: Point) -> Affine {
+        Affine::translate(center.x, center.y).then(self).then(&Affine::translate(-center.x, -center.y))
+    }
+
+    pub fn apply(&self, point: Point) -> Point {
+        Point::new(self.xx * point.x + self.xy * point.y + self.dx, self.yx * point.x + self.yy * point.y + self.dy)
+    }
+
+    // the outline moved by this transform, bounding box included
+    pub fn transform_glyph(&self, glyph: &Glyph) -> Glyph {
+        let contour_coordinates: Vec<Vec<(Point, u8)>> = glyph
+            .contour_coordinates
+            .iter()
+            .map(|contour| contour.iter().map(|(point, kind)| (self.apply(*point), *kind)).collect())
+            .collect();
+        let [x_min, y_min, x_max, y_max] = glyph.bounding_box;
+        let corners = [Point::new(x_min, y_min), Point::new(x_max, y_min), Point::new(x_min, y_max), Point::new(x_max, y_max)].map(|corner| self.apply(corner));
+        let bounding_box = corners.iter().fold([f32::MAX, f32::MAX, f32::MIN, f32::MIN], |[x0, y0, x1, y1], point| {
+            [x0.min(point.x), y0.min(point.y), x1.max(point.x), y1.max(point.y)]
+        });
+        Glyph { coordinates: Vec::new(), contour_end_pts: Vec::new(), contour_coordinates, bounding_box }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Extend {
+    Pad,
+    Repeat,
+    Reflect,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorStop {
+    pub offset: f32,
+    pub palette_index: Option<usize>, // None is the text color
+    pub alpha: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorLine {
+    pub extend: Extend,
+    pub stops: Vec<ColorStop>,
+}
+
+// https://learn.microsoft.com/en-us/typography/opentype/spec/colr#composition-modes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompositeMode {
+    Clear,
+    Source,
+    Destination,
+    SourceOver,
+    DestinationOver,
+    SourceIn,
+    DestinationIn,
+    SourceOut,
+    DestinationOut,
+    SourceAtop,
+    DestinationAtop,
+    Xor,
+    Plus,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Multiply,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+const COMPOSITE_MODES: [CompositeMode; 28] = [
+    CompositeMode::Clear, CompositeMode::Source, CompositeMode::Destination, CompositeMode::SourceOver,
+    CompositeMode::DestinationOver, CompositeMode::SourceIn, CompositeMode::DestinationIn, CompositeMode::SourceOut,
+    CompositeMode::DestinationOut, CompositeMode::SourceAtop, CompositeMode::DestinationAtop, CompositeMode::Xor,
+    CompositeMode::Plus, CompositeMode::Screen, CompositeMode::Overlay, CompositeMode::Darken, CompositeMode::Lighten,
+    CompositeMode::ColorDodge, CompositeMode::ColorBurn, CompositeMode::HardLight, CompositeMode::SoftLight,
+    CompositeMode::Difference, CompositeMode::Exclusion, CompositeMode::Multiply, CompositeMode::Hue,
+    CompositeMode::Saturation, CompositeMode::Color, CompositeMode::Luminosity,
+];
+
+// https://learn.microsoft.com/en-us/typography/opentype/spec/colr#paint-tables
+// the COLRv1 paint graph of one glyph. points are scaled like the outlines, angles are in degrees.
+// the variable formats are read with their default values, PaintColrGlyph is inlined and all the
+// translate/scale/rotate/skew formats end up as Transform
+#[derive(Clone, Debug, PartialEq)]
+pub enum Paint {
+    Layers(Vec<Paint>), // bottom to top
+    Solid { palette_index: Option<usize>, alpha: f32 },
+    LinearGradient { color_line: ColorLine, p0: Point, p1: Point, p2: Point }, // p2 rotates the gradient
+    RadialGradient { color_line: ColorLine, c0: Point, r0: f32, c1: Point, r1: f32 },
+    SweepGradient { color_line: ColorLine, center: Point, start_angle: f32, end_angle: f32 },
+    Glyph { glyph_index: usize, paint: Box<Paint> }, // paint clipped to the glyph's outline
+    Transform { transform: Affine, paint: Box<Paint> },
+    Composite { source: Box<Paint>, mode: CompositeMode, backdrop: Box<Paint> },
+}
+
+// what color_paint hands out, the graph and the area it may paint in
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorGlyph {
+    pub paint: Paint,
+    pub clip_box: Option<[f32; 4]>, // x_min, y_min, x_max, y_max scaled like the outlines, None when the ClipList has none for the glyph
+}
+
+// one outline out of a paint graph with a single color standing in for whatever fills it,
+// what the gizmo renderer can show of a COLRv1 glyph
+#[derive(Clone, Copy, Debug)]
+pub struct PaintedGlyph {
+    pub glyph_index: usize,
+    pub transform: Affine,
+    pub color: PaletteColor,
+}
+
+impl Paint {
+    // every glyph in the graph in drawing order. gradients become the average of their stops and
+    // composites are drawn as the source over the backdrop whatever their mode
+    pub fn flatten(&self, palette: &[PaletteColor], foreground: PaletteColor) -> Vec<PaintedGlyph> {
+        let mut glyphs = Vec::new();
+        self.flatten_into(Affine::IDENTITY, palette, foreground, &mut glyphs);
+        glyphs
+    }
+
+    fn flatten_into(&self, transform: Affine, palette: &[PaletteColor], foreground: PaletteColor, glyphs: &mut Vec<PaintedGlyph>) {
+        match self {
+            Paint::Layers(layers) => layers.iter().for_each(|layer| layer.flatten_into(transform, palette, foreground, glyphs)),
+            Paint::Glyph { glyph_index, paint } => glyphs.push(PaintedGlyph { glyph_index: *glyph_index, transform, color: paint.average_color(palette, foreground) }),
+            Paint::Transform { transform: inner, paint } => paint.flatten_into(transform.then(inner), palette, foreground, glyphs),
+            Paint::Composite { source, backdrop, .. } => {
+                backdrop.flatten_into(transform, palette, foreground, glyphs);
+                source.flatten_into(transform, palette, foreground, glyphs);
+            }
+            // a fill with no glyph to clip it covers the whole clip box, nothing to outline
+            Paint::Solid { .. } | Paint::LinearGradient { .. } | Paint::RadialGradient { .. } | Paint::SweepGradient { .. } => (),
+        }
+    }
+
+    fn average_color(&self, palette: &[PaletteColor], foreground: PaletteColor) -> PaletteColor {
+        let color_of = |palette_index: Option<usize>, alpha: f32| {
+            let color = palette_index.map_or(foreground, |index| palette.get(index).copied().unwrap_or(foreground));
+            PaletteColor { a: (color.a as f32 * alpha.clamp(0.0, 1.0)) as u8, ..color }
+        };
+        match self {
+            Paint::Solid { palette_index, alpha } => color_of(*palette_index, *alpha),
+            Paint::LinearGradient { color_line, .. } | Paint::RadialGradient { color_line, .. } | Paint::SweepGradient { color_line, .. } => {
+                let colors: Vec<PaletteColor> = color_line.stops.iter().map(|stop| color_of(stop.palette_index, stop.alpha)).collect();
+                let count = colors.len().max(1) as u32;
+                let channel = |pick: fn(&PaletteColor) -> u8| (colors.iter().map(|color| pick(color) as u32).sum::<u32>() / count) as u8;
+                PaletteColor { r: channel(|color| color.r), g: channel(|color| color.g), b: channel(|color| color.b), a: channel(|color| color.a) }
+            }
+            Paint::Layers(layers) => layers.last().map_or(foreground, |layer| layer.average_color(palette, foreground)),
+            Paint::Glyph { paint, .. } | Paint::Transform { paint, .. } => paint.average_color(palette, foreground),
+            Paint::Composite { source, .. } => source.average_color(palette, foreground),
+        }
+    }
+}
+
+impl FontData {
+    // https://learn.microsoft.com/en-us/typography/opentype/spec/colr#baseglyphlist-layerlist-and-cliplist
+    // None for glyphs without a COLRv1 paint (and fonts with only version 0 layers)
+    pub fn color_paint(&self, glyph_index: usize) -> Result<Option<ColorGlyph>, FontError> {
+        if !self.font_table.contains_key(&COLR) {
+            return Ok(None);
+        }
+        let colr = self.table(COLR)?;
+        let mut header = colr;
+        if header.read_u16()? == 0 {
+            return Ok(None);
+        }
+        header.go_to(14)?;
+        let base_glyph_list = header.read_offset32()?.to_usize();
+        let layer_list = header.read_offset32()?.to_usize();
+        let clip_list = header.read_offset32()?.to_usize();
+        if base_glyph_list == 0 {
+            return Ok(None);
+        }
+
+        let parser = PaintParser { colr, base_glyph_list, layer_list, font_scale: self.font_scale, nodes: Cell::new(0) };
+        let Some(position) = parser.base_paint(glyph_index)? else {
+            return Ok(None);
+        };
+        let paint = parser.paint(position, 0)?;
+        let clip_box = if clip_list == 0 { None } else { self.clip_box(colr, clip_list, glyph_index)? };
+        Ok(Some(ColorGlyph { paint, clip_box }))
+    }
+
+    // out of the ClipList, its records cover runs of glyphs that share a box
+    fn clip_box(&self, colr: FontReader, clip_list: usize, glyph_index: usize) -> Result<Option<[f32; 4]>, FontError> {
+        let mut reader = colr.reader_from(clip_list)?;
+        reader.skip_bytes(1)?; // format
+        for _ in 0..reader.read_u32()? {
+            let (start, end) = (reader.read_u16()? as usize, reader.read_u16()? as usize);
+            let offset = reader.read_offset24()?.to_usize();
+            if (start..=end).contains(&glyph_index) {
+                let mut clip_box = colr.reader_from(clip_list + offset)?;
+                clip_box.skip_bytes(1)?; // format, 2 only adds variations
+                let mut next = || clip_box.read_i16().map(|value| value as f32 * self.font_scale);
+                return Ok(Some([next()?, next()?, next()?, next()?]));
+            }
+        }
+        Ok(None)
+    }
+}
+
+struct PaintParser<'a> {
+    colr: FontReader<'a>,
+    base_glyph_list: usize,
+    layer_list: usize,
+    font_scale: f32,
+    nodes: Cell<usize>, // paints read so far, see MAX_PAINT_NODES
+}
+
+impl PaintParser<'_> {
+    // where the glyph's paint starts in the table, the records are sorted by glyph
+    fn base_paint(&self, glyph_index: usize) -> Result<Option<usize>, FontError> {
+        let mut list = self.colr.reader_from(self.base_glyph_list)?;
+        let (mut low, mut high) = (0, list.read_u32()? as usize);
+        while low < high {
+            let middle = (low + high) / 2;
+            let mut record = list.sub_reader(4 + middle * 6, 6)?;
+            let base_glyph = record.read_u16()? as usize;
+            if base_glyph < glyph_index {
+                low = middle + 1;
+            } else if base_glyph > glyph_index {
+                high = middle;
+            } else {
+                return Ok(Some(self.base_glyph_list + record.read_offset32()?.to_usize()));
+            }
+        }
+        Ok(None)
+    }
+
+    // the paint starting at position (from the start of COLR)
+    fn paint(&self, position: usize, depth: usize) -> Result<Paint, FontError> {
+        if depth > MAX_PAINT_DEPTH {
+            return Err(FontError::Parse("COLR paint graph is too deep (or has a cycle)".to_string()));
+        }
+        self.nodes.set(self.nodes.get() + 1);
+        if self.nodes.get() > MAX_PAINT_NODES {
+            return Err(FontError::Parse("COLR paint graph has too many paints".to_string()));
+        }
+        let mut reader = self.colr.reader_from(position)?;
+        let format = reader.read_byte()?;
+        // the variable formats come right after their plain one and start with the same fields
+        let variable = matches!(format, 2..=9 | 12..=31) && format % 2 == 1;
+        let format = if variable { format - 1 } else { format };
+        let scaled = |value: i16| value as f32 * self.font_scale;
+        let degrees = |value: f32| value * 180.0;
+
+        let paint = match format {
+            1 => {
+                let count = reader.read_byte()? as usize;
+                let first = reader.read_u32()? as usize;
+                let mut offsets = self.colr.reader_from(self.layer_list + 4 + first * 4)?;
+                let mut layers = Vec::with_capacity(count);
+                for _ in 0..count {
+                    layers.push(self.paint(self.layer_list + offsets.read_offset32()?.to_usize(), depth + 1)?);
+                }
+                Paint::Layers(layers)
+            }
+            2 => Paint::Solid { palette_index: palette_index(reader.read_u16()?), alpha: reader.read_f2dot14()?.to_f32() },
+            4 => {
+                let color_line = self.color_line(position + reader.read_offset24()?.to_usize(), variable)?;
+                let mut point = || -> std::io::Result<Point> { Ok(Point::new(scaled(reader.read_i16()?), scaled(reader.read_i16()?))) };
+                Paint::LinearGradient { color_line, p0: point()?, p1: point()?, p2: point()? }
+            }
+            6 => {
+                let color_line = self.color_line(position + reader.read_offset24()?.to_usize(), variable)?;
+                let c0 = Point::new(scaled(reader.read_i16()?), scaled(reader.read_i16()?));
+                let r0 = reader.read_u16()? as f32 * self.font_scale;
+                let c1 = Point::new(scaled(reader.read_i16()?), scaled(reader.read_i16()?));
+                let r1 = reader.read_u16()? as f32 * self.font_scale;
+                Paint::RadialGradient { color_line, c0, r0, c1, r1 }
+            }
+            8 => {
+                let color_line = self.color_line(position + reader.read_offset24()?.to_usize(), variable)?;
+                let center = Point::new(scaled(reader.read_i16()?), scaled(reader.read_i16()?));
+                let (start_angle, end_angle) = (degrees(reader.read_f2dot14()?.to_f32()), degrees(reader.read_f2dot14()?.to_f32()));
+                Paint::SweepGradient { color_line, center, start_angle, end_angle }
+            }
+            10 => {
+                let paint = self.child(&mut reader, position, depth)?;
+                Paint::Glyph { glyph_index: reader.read_u16()? as usize, paint }
+            }
+            11 => match self.base_paint(reader.read_u16()? as usize)? {
+                Some(base) => self.paint(base, depth + 1)?,
+                None => Paint::Layers(Vec::new()),
+            },
+            12 => {
+                let paint = self.child(&mut reader, position, depth)?;
+                let mut matrix = self.colr.reader_from(position + reader.read_offset24()?.to_usize())?;
+                let mut next = || matrix.read_fixed().map(|value| value.to_f32());
+                let (xx, yx, xy, yy, dx, dy) = (next()?, next()?, next()?, next()?, next()?, next()?);
+                Paint::Transform { transform: Affine { xx, yx, xy, yy, dx: dx * self.font_scale, dy: dy * self.font_scale }, paint }
+            }
+            14 => {
+                let paint = self.child(&mut reader, position, depth)?;
+                Paint::Transform { transform: Affine::translate(scaled(reader.read_i16()?), scaled(reader.read_i16()?)), paint }
+            }
+            16 | 18 => {
+                let paint = self.child(&mut reader, position, depth)?;
+                let transform = Affine::scale(reader.read_f2dot14()?.to_f32(), reader.read_f2dot14()?.to_f32());
+                Paint::Transform { transform: self.maybe_around(transform, &mut reader, format == 18)?, paint }
+            }
+            20 | 22 => {
+                let paint = self.child(&mut reader, position, depth)?;
+                let scale = reader.read_f2dot14()?.to_f32();
+                Paint::Transform { transform: self.maybe_around(Affine::scale(scale, scale), &mut reader, format == 22)?, paint }
+            }
+            24 | 26 => {
+                let paint = self.child(&mut reader, position, depth)?;
+                let transform = Affine::rotate(degrees(reader.read_f2dot14()?.to_f32()));
+                Paint::Transform { transform: self.maybe_around(transform, &mut reader, format == 26)?, paint }
+            }
+            28 | 30 => {
+                let paint = self.child(&mut reader, position, depth)?;
+                let transform = Affine::skew(degrees(reader.read_f2dot14()?.to_f32()), degrees(reader.read_f2dot14()?.to_f32()));
+                Paint::Transform { transform: self.maybe_around(transform, &mut reader, format == 30)?, paint }
+            }
+            32 => {
+                let source = self.child(&mut reader, position, depth)?;
+                let mode = COMPOSITE_MODES.get(reader.read_byte()? as usize).copied().unwrap_or(CompositeMode::SourceOver);
+                let backdrop = self.child(&mut reader, position, depth)?;
+                Paint::Composite { source, mode, backdrop }
+            }
+            _ => return Err(FontError::Parse(format!("unknown COLR paint format {format}"))),
+        };
+        Ok(paint)
+    }
+
+    // the paint an Offset24 in the current one points to
+    fn child(&self, reader: &mut FontReader, position: usize, depth: usize) -> Result<Box<Paint>, FontError> {
+        let offset = reader.read_offset24()?.to_usize();
+        Ok(Box::new(self.paint(position + offset, depth + 1)?))
+    }
+
+    // the *AroundCenter formats end with the center
+    fn maybe_around(&self, transform: Affine, reader: &mut FontReader, around_center: bool) -> Result<Affine, FontError> {
+        if !around_center {
+            return Ok(transform);
+        }
+        let center = Point::new(reader.read_i16()? as f32 * self.font_scale, reader.read_i16()? as f32 * self.font_scale);
+        Ok(transform.around(center))
+    }
+
+    fn color_line(&self, position: usize, variable: bool) -> Result<ColorLine, FontError> {
+        let mut reader = self.colr.reader_from(position)?;
+        let extend = match reader.read_byte()? {
+            1 => Extend::Repeat,
+            2 => Extend::Reflect,
+            _ => Extend::Pad,
+        };
+        let count = reader.read_u16()?;
+        let mut stops = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let offset = reader.read_f2dot14()?.to_f32();
+            let palette_index = palette_index(reader.read_u16()?);
+            let alpha = reader.read_f2dot14()?.to_f32();
+            if variable {
+                reader.skip_bytes(4)?; // varIndexBase
+            }
+            stops.push(ColorStop { offset, palette_index, alpha });
+        }
+        Ok(ColorLine { extend, stops })
+    }
+}
+
+fn palette_index(index: u16) -> Option<usize> {
+    (index != FOREGROUND).then_some(index as usize)
+}
@@ -0,0 +1,118 @@
+use std::sync::OnceLock;
+
+use crate::font_reader::{Fixed, FontReader, Tag};
+use crate::font_table_parser::{FontData, FontError};
+
+pub const POST: Tag = Tag::new(b"post");
+
+// the 258 glyph names every version 1 font uses in this order, version 2 fonts point into them for the usual latin ones.
+// https://developer.apple.com/fonts/TrueType-Reference-Manual/RM06/Chap6post.html
+const MAC_NAMES: &str = "\
+    .notdef .null nonmarkingreturn space exclam quotedbl numbersign dollar percent ampersand quotesingle \
+    parenleft parenright asterisk plus comma hyphen period slash zero one two three four five six seven eight \
+    nine colon semicolon less equal greater question at A B C D E F G H I J K L M N O P Q R S T U V W X Y Z \
+    bracketleft backslash bracketright asciicircum underscore grave a b c d e f g h i j k l m n o p q r s t u v w \
+    x y z braceleft bar braceright asciitilde Adieresis Aring Ccedilla Eacute Ntilde Odieresis Udieresis aacute \
+    agrave acircumflex adieresis atilde aring ccedilla eacute egrave ecircumflex edieresis iacute igrave \
+    icircumflex idieresis ntilde oacute ograve ocircumflex odieresis otilde uacute ugrave ucircumflex udieresis \
+    dagger degree cent sterling section bullet paragraph germandbls registered copyright trademark acute dieresis \
+    notequal AE Oslash infinity plusminus lessequal greaterequal yen mu partialdiff summation product pi integral \
+    ordfeminine ordmasculine Omega ae oslash questiondown exclamdown logicalnot radical florin approxequal Delta \
+    guillemotleft guillemotright ellipsis nonbreakingspace Agrave Atilde Otilde OE oe endash emdash quotedblleft \
+    quotedblright quoteleft quoteright divide lozenge ydieresis Ydieresis fraction currency guilsinglleft \
+    guilsinglright fi fl daggerdbl periodcentered quotesinglbase quotedblbase perthousand Acircumflex Ecircumflex \
+    Aacute Edieresis Egrave Iacute Icircumflex Idieresis Igrave Oacute Ocircumflex apple Ograve Uacute \
+    Ucircumflex Ugrave dotlessi circumflex tilde macron breve dotaccent ring cedilla hungarumlaut ogonek caron \
+    Lslash lslash Scaron scaron Zcaron zcaron brokenbar Eth eth Yacute yacute Thorn thorn minus multiply \
+    onesuperior twosuperior threesuperior onehalf onequarter threequarters franc Gbreve gbreve Idotaccent \
+    Scedilla scedilla Cacute cacute Ccaron ccaron dcroat";
+
+fn mac_names() -> &'static [&'static str] {
+    static NAMES: OnceLock<Vec<&str>> = OnceLock::new();
+    NAMES.get_or_init(|| MAC_NAMES.split_whitespace().collect())
+}
+
+// https://learn.microsoft.com/en-us/typography/opentype/spec/post
+// the glyph names are read separately by FontData::glyph_name, the first time they are asked for
+#[derive(Clone, Debug, Default)]
+pub struct Post {
+    pub version: Fixed, // 1.0, 2.0, 2.5 or 3.0 (no names)
+    pub italic_angle: f32, // degrees counterclockwise from vertical, negative for fonts that lean right
+    pub underline_position: i16, // font units, top of the underline relative to the baseline
+    pub underline_thickness: i16,
+    pub is_fixed_pitch: bool,
+}
+
+impl Post {
+    pub fn parse(mut reader: FontReader) -> std::io::Result<Self> {
+        Ok(Post {
+            version: reader.read_fixed()?,
+            italic_angle: reader.read_fixed()?.to_f32(),
+            underline_position: reader.read_i16()?,
+            underline_thickness: reader.read_i16()?,
+            is_fixed_pitch: reader.read_u32()? != 0,
+        })
+    }
+}
+
+impl FontData {
+    // post is optional for us, fonts without one just have no glyph names
+    pub fn get_post(&mut self) -> Result<(), FontError> {
+        if self.font_table.contains_key(&POST) {
+            self.post = Some(Post::parse(self.table(POST)?)?);
+        }
+        Ok(())
+    }
+
+    // None for version 3 tables, fonts without post and names the table doesn't have
+    pub fn glyph_name(&self, glyph_index: usize) -> Option<&str> {
+        let names = self.glyph_names.get_or_init(|| self.read_glyph_names().unwrap_or_default());
+        names.get(glyph_index).map(String::as_str).filter(|name| !name.is_empty())
+    }
+
+    // one name per glyph, empty where there isn't one
+    fn read_glyph_names(&self) -> Result<Vec<String>, FontError> {
+        let Some(post) = &self.post else { return Ok(Vec::new()) };
+        let mut reader = self.table(POST)?;
+        reader.go_to(32)?; // past the header and the memory usage fields
+        let mac_names = mac_names();
+
+        match post.version.0 {
+            0x00010000 => Ok(mac_names.iter().take(self.num_glyphs).map(|name| name.to_string()).collect()),
+            0x00020000 => {
+                let count = reader.read_u16()? as usize;
+                let mut indices = Vec::with_capacity(count);
+                for _ in 0..count {
+                    indices.push(reader.read_u16()? as usize);
+                }
+
+                // pascal strings, a length byte and then that many bytes
+                let mut custom_names = Vec::new();
+                while let Ok(length) = reader.read_byte() {
+                    let Ok(bytes) = reader.sub_reader(reader.get_location(), length as usize) else { break };
+                    custom_names.push(String::from_utf8_lossy(bytes.data()).into_owned());
+                    reader.skip_bytes(length as usize)?;
+                }
+
+                Ok(indices
+                    .into_iter()
+                    .map(|index| match index.checked_sub(mac_names.len()) {
+                        None => mac_names[index].to_string(),
+                        Some(custom) => custom_names.get(custom).cloned().unwrap_or_default(),
+                    })
+                    .collect())
+            }
+            // deprecated, each glyph's name is the mac name at glyph index + offset
+            0x00025000 => {
+                let count = reader.read_u16()? as usize;
+                let mut names = Vec::with_capacity(count);
+                for glyph_index in 0..count {
+                    let index = glyph_index as isize + reader.read_i8()? as isize;
+                    names.push(usize::try_from(index).ok().and_then(|index| mac_names.get(index)).map_or(String::new(), |name| name.to_string()));
+                }
+                Ok(names)
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+}
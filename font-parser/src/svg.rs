@@ -0,0 +1,672 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_4, TAU};
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+
+use crate::color::PaletteColor;
+use crate::font_reader::Tag;
+use crate::font_table_parser::{setup_implied_points, FontData, FontError, Glyph};
+use crate::geometry::Point;
+use crate::paint::Affine;
+
+pub const SVG: Tag = Tag::new(b"SVG ");
+
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+const MAX_DOCUMENT_SIZE: u64 = 32 << 20; // gzip can blow a few bytes up into gigabytes
+const MAX_DEPTH: usize = 32; // nesting and <use> chains
+const MAX_ELEMENTS_DRAWN: usize = 10_000; // per glyph, <use> can draw the same elements over and over
+const CUBIC_PIECES: usize = 4; // quadratics per cubic, plenty at text sizes
+
+// one filled shape of an SVG glyph, drawn bottom to top like COLR layers
+#[derive(Clone)]
+pub struct SvgShape {
+    pub glyph: Glyph, // already in outline units, y up
+    pub color: Option<PaletteColor>, // None is currentColor, the text color
+}
+
+impl FontData {
+    // https://learn.microsoft.com/en-us/typography/opentype/spec/svg
+    // the document glyph_index is in, gunzipped when it was compressed. one document can hold many glyphs
+    pub fn svg_document(&self, glyph_index: usize) -> Result<Option<String>, FontError> {
+        if !self.font_table.contains_key(&SVG) {
+            return Ok(None);
+        }
+        let svg = self.table(SVG)?;
+        let mut header = svg;
+        header.skip_bytes(2)?; // version
+        let document_list = svg.reader_from(header.read_offset32()?.to_usize())?;
+        let mut records = document_list;
+        let num_entries = records.read_u16()? as usize;
+
+        // records are sorted and don't overlap
+        let (mut low, mut high) = (0, num_entries);
+        while low < high {
+            let middle = (low + high) / 2;
+            let mut record = document_list.sub_reader(2 + middle * 12, 12)?;
+            let (start_glyph, end_glyph) = (record.read_u16()? as usize, record.read_u16()? as usize);
+            if end_glyph < glyph_index {
+                low = middle + 1;
+            } else if start_glyph > glyph_index {
+                high = middle;
+            } else {
+                let (offset, length) = (record.read_offset32()?.to_usize(), record.read_u32()? as usize);
+                let bytes = document_list.sub_reader(offset, length)?.data();
+                if !bytes.starts_with(&GZIP_MAGIC) {
+                    return Ok(Some(String::from_utf8_lossy(bytes).into_owned()));
+                }
+                let mut text = Vec::new();
+                GzDecoder::new(bytes).take(MAX_DOCUMENT_SIZE).read_to_end(&mut text)?;
+                return Ok(Some(String::from_utf8_lossy(&text).into_owned()));
+            }
+        }
+        Ok(None)
+    }
+
+    // the glyph's shapes out of its document, None when the font has no SVG version of it.
+    // only the parts fonts actually use: paths and basic shapes, groups, <use>, transforms and solid fills.
+    // gradients become the average of their stops like in COLRv1, strokes, clips, masks and filters are left out
+    pub fn svg_glyph(&self, glyph_index: usize) -> Result<Option<Vec<SvgShape>>, FontError> {
+        let Some(text) = self.svg_document(glyph_index)? else { return Ok(None) };
+        let Some(root) = parse_xml(&text) else {
+            return Err(FontError::Parse(format!("the SVG document of glyph {glyph_index} is not valid xml (or nests too deep)")));
+        };
+        let ids = root.ids();
+        let Some(element) = ids.get(format!("glyph{glyph_index}").as_str()).copied() else { return Ok(None) };
+
+        // glyphs are drawn in font units with y going down from the baseline
+        let style = Style {
+            transform: Affine::scale(self.font_scale, -self.font_scale),
+            fill: Fill::Color(PaletteColor { r: 0, g: 0, b: 0, a: 255 }),
+            fill_opacity: 1.0,
+            opacity: 1.0,
+        };
+        let mut shapes = Vec::new();
+        walk(&ids, element, style, 0, &mut 0, &mut shapes);
+        Ok(Some(shapes))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Fill {
+    None,
+    Color(PaletteColor),
+    CurrentColor,
+}
+
+// what an element passes down to its children
+#[derive(Clone, Copy)]
+struct Style {
+    transform: Affine,
+    fill: Fill,
+    fill_opacity: f32,
+    opacity: f32, // not inherited in svg, but a group's opacity ends up on everything in it
+}
+
+// drawn counts the elements walked so far, see MAX_ELEMENTS_DRAWN
+fn walk(ids: &Ids, element: &Element, parent: Style, depth: usize, drawn: &mut usize, shapes: &mut Vec<SvgShape>) {
+    if depth > MAX_DEPTH || *drawn >= MAX_ELEMENTS_DRAWN || element.property("display") == Some("none") {
+        return;
+    }
+    *drawn += 1;
+    // only drawn when something refers to them
+    if matches!(element.name.as_str(), "defs" | "clipPath" | "mask" | "linearGradient" | "radialGradient" | "pattern" | "filter" | "symbol" | "style") {
+        return;
+    }
+
+    let mut style = parent;
+    if let Some(transform) = element.attribute("transform") {
+        style.transform = parent.transform.then(&parse_transform(transform));
+    }
+    if let Some(fill) = element.property("fill").and_then(|fill| parse_fill(ids, fill)) {
+        style.fill = fill;
+    }
+    if let Some(fill_opacity) = element.property("fill-opacity").and_then(parse_number) {
+        style.fill_opacity = fill_opacity.clamp(0.0, 1.0);
+    }
+    if let Some(opacity) = element.property("opacity").and_then(parse_number) {
+        style.opacity *= opacity.clamp(0.0, 1.0);
+    }
+
+    let path = match element.name.as_str() {
+        "path" => element.attribute("d").map(Cow::Borrowed),
+        "rect" | "circle" | "ellipse" | "polygon" | "polyline" => shape_path(element).map(Cow::Owned),
+        "use" => {
+            let Some(target) = element.attribute("href").or(element.attribute("xlink:href")).and_then(|href| ids.get(href.strip_prefix('#')?).copied()) else { return };
+            let offset = Affine::translate(element.number("x"), element.number("y"));
+            let style = Style { transform: style.transform.then(&offset), ..style };
+            if target.name == "symbol" {
+                target.children.iter().for_each(|child| walk(ids, child, style, depth + 1, drawn, shapes));
+            } else {
+                walk(ids, target, style, depth + 1, drawn, shapes);
+            }
+            return;
+        }
+        _ => None, // svg, g and friends only group their children
+    };
+
+    if let Some(path) = path {
+        let color = match style.fill {
+            Fill::None => None,
+            Fill::Color(color) => Some(Some(PaletteColor { a: (color.a as f32 * style.fill_opacity * style.opacity) as u8, ..color })),
+            Fill::CurrentColor => Some(None),
+        };
+        let glyph = path_outline(&path, &style.transform);
+        if let Some(color) = color.filter(|_| !glyph.contour_coordinates.is_empty()) {
+            shapes.push(SvgShape { glyph, color });
+        }
+    }
+    for child in &element.children {
+        walk(ids, child, style, depth + 1, drawn, shapes);
+    }
+}
+
+// the basic shapes as path data, so there's only one outline builder
+fn shape_path(element: &Element) -> Option<String> {
+    let number = |name| element.number(name);
+    match element.name.as_str() {
+        "rect" => {
+            let (x, y, width, height) = (number("x"), number("y"), number("width"), number("height"));
+            if width <= 0.0 || height <= 0.0 {
+                return None;
+            }
+            // a missing rx or ry is the same as the other one
+            let (rx, ry) = match (element.attribute("rx").and_then(parse_number), element.attribute("ry").and_then(parse_number)) {
+                (Some(rx), Some(ry)) => (rx, ry),
+                (Some(r), None) | (None, Some(r)) => (r, r),
+                (None, None) => (0.0, 0.0),
+            };
+            let (rx, ry) = (rx.clamp(0.0, width / 2.0), ry.clamp(0.0, height / 2.0));
+            if rx == 0.0 || ry == 0.0 {
+                return Some(format!("M{x} {y}h{width}v{height}h{}z", -width));
+            }
+            let (right, bottom) = (x + width, y + height);
+            Some(format!(
+                "M{} {y}H{}A{rx} {ry} 0 0 1 {right} {}V{}A{rx} {ry} 0 0 1 {} {bottom}H{}A{rx} {ry} 0 0 1 {x} {}V{}A{rx} {ry} 0 0 1 {} {y}z",
+                x + rx, right - rx, y + ry, bottom - ry, right - rx, x + rx, bottom - ry, y + ry, x + rx
+            ))
+        }
+        "circle" | "ellipse" => {
+            let (cx, cy) = (number("cx"), number("cy"));
+            let (rx, ry) = if element.name == "circle" { (number("r"), number("r")) } else { (number("rx"), number("ry")) };
+            if rx <= 0.0 || ry <= 0.0 {
+                return None;
+            }
+            Some(format!("M{} {cy}A{rx} {ry} 0 1 0 {} {cy}A{rx} {ry} 0 1 0 {} {cy}z", cx - rx, cx + rx, cx - rx))
+        }
+        // polylines are open but filling closes them anyway
+        _ => element.attribute("points").map(|points| format!("M{points}z")),
+    }
+}
+
+// contours in the same shape glyf outlines end up in, with the transform already applied
+#[derive(Default)]
+struct OutlineBuilder {
+    transform: Affine,
+    coordinates: Vec<(Point, bool)>,
+    contour_end_pts: Vec<u16>,
+    contour_start: usize,
+}
+
+impl OutlineBuilder {
+    fn push(&mut self, point: Point, on_curve: bool) {
+        self.coordinates.push((self.transform.apply(point), on_curve));
+    }
+
+    fn close(&mut self) {
+        let contour = &self.coordinates[self.contour_start..];
+        if contour.len() > 1 && contour[contour.len() - 1] == contour[0] {
+            self.coordinates.pop(); // closing back onto the start would only add a zero length segment
+        }
+        if self.coordinates.len() - self.contour_start < 3 || self.coordinates.len() > u16::MAX as usize {
+            self.coordinates.truncate(self.contour_start); // too small to fill anything
+        } else {
+            self.contour_end_pts.push((self.coordinates.len() - 1) as u16);
+        }
+        self.contour_start = self.coordinates.len();
+    }
+
+    // a curve given as a function from 0..1, as quadratics that go through its middle points. works for cubics and
+    // arcs alike, and affine transforms keep that property so transforming the points afterwards is fine
+    fn curve(&mut self, at: impl Fn(f32) -> Point, pieces: usize) {
+        for piece in 0..pieces {
+            let (start, end) = (piece as f32 / pieces as f32, (piece + 1) as f32 / pieces as f32);
+            let control = at((start + end) / 2.0) * 2.0 - (at(start) + at(end)) * 0.5;
+            self.push(control, false);
+            self.push(at(end), true);
+        }
+    }
+
+    // https://www.w3.org/TR/SVG11/implnote.html#ArcConversionEndpointToCenter
+    fn arc(&mut self, from: Point, (rx, ry): (f32, f32), rotation: f32, large_arc: bool, sweep: bool, to: Point) {
+        if from == to {
+            return;
+        }
+        let (mut rx, mut ry) = (rx.abs(), ry.abs());
+        if rx == 0.0 || ry == 0.0 {
+            self.push(to, true);
+            return;
+        }
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        let half = (from - to) * 0.5;
+        let (x, y) = (cos * half.x + sin * half.y, -sin * half.x + cos * half.y);
+        let lambda = (x * x) / (rx * rx) + (y * y) / (ry * ry);
+        if lambda > 1.0 {
+            (rx, ry) = (rx * lambda.sqrt(), ry * lambda.sqrt());
+        }
+        let numerator = rx * rx * ry * ry - rx * rx * y * y - ry * ry * x * x;
+        let denominator = rx * rx * y * y + ry * ry * x * x;
+        let mut coefficient = (numerator / denominator).max(0.0).sqrt();
+        if large_arc == sweep {
+            coefficient = -coefficient;
+        }
+        let (center_x, center_y) = (coefficient * rx * y / ry, -coefficient * ry * x / rx);
+        let middle = (from + to) * 0.5;
+        let center = Point::new(cos * center_x - sin * center_y + middle.x, sin * center_x + cos * center_y + middle.y);
+
+        let angle = |ux: f32, uy: f32, vx: f32, vy: f32| (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+        let start_angle = angle(1.0, 0.0, (x - center_x) / rx, (y - center_y) / ry);
+        let mut sweep_angle = angle((x - center_x) / rx, (y - center_y) / ry, (-x - center_x) / rx, (-y - center_y) / ry);
+        if !sweep && sweep_angle > 0.0 {
+            sweep_angle -= TAU;
+        } else if sweep && sweep_angle < 0.0 {
+            sweep_angle += TAU;
+        }
+
+        let pieces = (sweep_angle.abs() / FRAC_PI_4).ceil().max(1.0) as usize;
+        self.curve(|t| {
+            let (sin_t, cos_t) = (start_angle + sweep_angle * t).sin_cos();
+            Point::new(center.x + rx * cos_t * cos - ry * sin_t * sin, center.y + rx * cos_t * sin + ry * sin_t * cos)
+        }, pieces);
+    }
+
+    fn finish(mut self) -> Glyph {
+        self.close();
+        let bounding_box = self.coordinates.iter().fold([f32::MAX, f32::MAX, f32::MIN, f32::MIN], |[x0, y0, x1, y1], (point, _)| {
+            [x0.min(point.x), y0.min(point.y), x1.max(point.x), y1.max(point.y)]
+        });
+        let mut glyph = Glyph { coordinates: self.coordinates, contour_end_pts: self.contour_end_pts, contour_coordinates: Vec::new(), bounding_box };
+        setup_implied_points(std::slice::from_mut(&mut glyph));
+        glyph
+    }
+}
+
+// https://www.w3.org/TR/SVG11/paths.html#PathData
+// stops at the first thing that doesn't parse and keeps what came before, like browsers do
+fn path_outline(data: &str, transform: &Affine) -> Glyph {
+    let mut outline = OutlineBuilder { transform: *transform, ..Default::default() };
+    let mut tokens = PathTokens { bytes: data.as_bytes(), position: 0 };
+    let (mut current, mut start) = (Point::ZERO, Point::ZERO);
+    let mut last_control: Option<(u8, Point)> = None; // for the smooth S and T commands
+    let mut command = None;
+
+    loop {
+        tokens.skip_separators();
+        if tokens.position >= tokens.bytes.len() {
+            break;
+        }
+        if let Some(next) = tokens.command() {
+            command = Some(next);
+        }
+        let Some(letter) = command else { break };
+        let relative = letter.is_ascii_lowercase();
+        let base = if relative { current } else { Point::ZERO };
+
+        let control = match letter.to_ascii_uppercase() {
+            b'M' => {
+                let Some(to) = tokens.point(base) else { break };
+                outline.close();
+                outline.push(to, true);
+                (current, start) = (to, to);
+                command = Some(if relative { b'l' } else { b'L' }); // more pairs after a move are lines
+                None
+            }
+            b'L' => {
+                let Some(to) = tokens.point(base) else { break };
+                outline.push(to, true);
+                current = to;
+                None
+            }
+            b'H' | b'V' => {
+                let Some(value) = tokens.number() else { break };
+                current = match (letter.to_ascii_uppercase(), relative) {
+                    (b'H', true) => Point::new(current.x + value, current.y),
+                    (b'H', false) => Point::new(value, current.y),
+                    (_, true) => Point::new(current.x, current.y + value),
+                    (_, false) => Point::new(current.x, value),
+                };
+                outline.push(current, true);
+                None
+            }
+            b'C' | b'S' => {
+                let first = match (letter.to_ascii_uppercase(), last_control) {
+                    (b'C', _) => match tokens.point(base) { Some(first) => first, None => break },
+                    (_, Some((b'C', control))) => current * 2.0 - control,
+                    _ => current,
+                };
+                let (Some(second), Some(to)) = (tokens.point(base), tokens.point(base)) else { break };
+                let from = current;
+                outline.curve(|t| {
+                    let u = 1.0 - t;
+                    from * (u * u * u) + first * (3.0 * u * u * t) + second * (3.0 * u * t * t) + to * (t * t * t)
+                }, CUBIC_PIECES);
+                current = to;
+                Some((b'C', second))
+            }
+            b'Q' | b'T' => {
+                let control = match (letter.to_ascii_uppercase(), last_control) {
+                    (b'Q', _) => match tokens.point(base) { Some(control) => control, None => break },
+                    (_, Some((b'Q', control))) => current * 2.0 - control,
+                    _ => current,
+                };
+                let Some(to) = tokens.point(base) else { break };
+                outline.push(control, false);
+                outline.push(to, true);
+                current = to;
+                Some((b'Q', control))
+            }
+            b'A' => {
+                let (Some(rx), Some(ry), Some(rotation)) = (tokens.number(), tokens.number(), tokens.number()) else { break };
+                let (Some(large_arc), Some(sweep)) = (tokens.flag(), tokens.flag()) else { break };
+                let Some(to) = tokens.point(base) else { break };
+                outline.arc(current, (rx, ry), rotation, large_arc, sweep, to);
+                current = to;
+                None
+            }
+            b'Z' => {
+                outline.close();
+                current = start;
+                command = None; // z takes no numbers, anything after it has to be a new command
+                None
+            }
+            _ => break,
+        };
+        last_control = control;
+    }
+    outline.finish()
+}
+
+struct PathTokens<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl PathTokens<'_> {
+    fn skip_separators(&mut self) {
+        while self.bytes.get(self.position).is_some_and(|byte| byte.is_ascii_whitespace() || *byte == b',') {
+            self.position += 1;
+        }
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.position)?;
+        (byte.is_ascii_alphabetic() && !matches!(byte, b'e' | b'E')).then(|| {
+            self.position += 1;
+            byte
+        })
+    }
+
+    // numbers can run into each other, 1-2.5.5 is 1, -2.5 and .5
+    fn number(&mut self) -> Option<f32> {
+        self.skip_separators();
+        let start = self.position;
+        let digits = |tokens: &mut Self| {
+            while tokens.bytes.get(tokens.position).is_some_and(u8::is_ascii_digit) {
+                tokens.position += 1;
+            }
+        };
+        if matches!(self.bytes.get(self.position), Some(b'-' | b'+')) {
+            self.position += 1;
+        }
+        digits(self);
+        if self.bytes.get(self.position) == Some(&b'.') {
+            self.position += 1;
+            digits(self);
+        }
+        if matches!(self.bytes.get(self.position), Some(b'e' | b'E')) && self.bytes.get(self.position + 1).is_some_and(|byte| byte.is_ascii_digit() || matches!(byte, b'-' | b'+')) {
+            self.position += 2;
+            digits(self);
+        }
+        let number = std::str::from_utf8(&self.bytes[start..self.position]).ok()?.parse().ok();
+        if number.is_none() {
+            self.position = start;
+        }
+        number
+    }
+
+    fn point(&mut self, base: Point) -> Option<Point> {
+        Some(Point::new(self.number()?, self.number()?) + base)
+    }
+
+    // arc flags are a single 0 or 1 and don't need anything between them
+    fn flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+        let flag = match self.bytes.get(self.position)? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.position += 1;
+        Some(flag)
+    }
+}
+
+// https://www.w3.org/TR/SVG11/coords.html#TransformAttribute
+fn parse_transform(text: &str) -> Affine {
+    let mut transform = Affine::IDENTITY;
+    for part in text.split(')') {
+        let Some((name, arguments)) = part.split_once('(') else { continue };
+        let values: Vec<f32> = arguments.split(|c: char| c == ',' || c.is_whitespace()).filter_map(parse_number).collect();
+        let value = |i: usize| values.get(i).copied().unwrap_or(0.0);
+        let next = match (name.trim_matches(|c: char| c == ',' || c.is_whitespace()), values.len()) {
+            ("matrix", 6) => Affine { xx: value(0), yx: value(1), xy: value(2), yy: value(3), dx: value(4), dy: value(5) },
+            ("translate", _) => Affine::translate(value(0), value(1)),
+            ("scale", 1) => Affine::scale(value(0), value(0)),
+            ("scale", _) => Affine::scale(value(0), value(1)),
+            ("rotate", 3) => Affine::translate(value(1), value(2)).then(&Affine::rotate(value(0))).then(&Affine::translate(-value(1), -value(2))),
+            ("rotate", _) => Affine::rotate(value(0)),
+            ("skewX", _) => Affine { xy: value(0).to_radians().tan(), ..Affine::IDENTITY },
+            ("skewY", _) => Affine { yx: value(0).to_radians().tan(), ..Affine::IDENTITY },
+            _ => continue,
+        };
+        transform = transform.then(&next);
+    }
+    transform
+}
+
+fn parse_fill(ids: &Ids, text: &str) -> Option<Fill> {
+    match text.trim() {
+        "none" => Some(Fill::None),
+        "currentColor" => Some(Fill::CurrentColor),
+        text if text.starts_with("url(") => {
+            let id = text.strip_prefix("url(")?.split(')').next()?.trim().trim_matches(['\'', '"']).strip_prefix('#')?;
+            Some(gradient_color(ids, ids.get(id)?, 0).map_or(Fill::None, Fill::Color))
+        }
+        text => parse_color(text).map(Fill::Color),
+    }
+}
+
+// gradients are averaged into one color, their stops can come from another gradient through href
+fn gradient_color(ids: &Ids, gradient: &Element, depth: usize) -> Option<PaletteColor> {
+    let stops: Vec<PaletteColor> = gradient
+        .children
+        .iter()
+        .filter(|child| child.name == "stop")
+        .map(|stop| {
+            let color = stop.property("stop-color").and_then(parse_color).unwrap_or(PaletteColor { r: 0, g: 0, b: 0, a: 255 });
+            let opacity = stop.property("stop-opacity").and_then(parse_number).unwrap_or(1.0).clamp(0.0, 1.0);
+            PaletteColor { a: (color.a as f32 * opacity) as u8, ..color }
+        })
+        .collect();
+    if stops.is_empty() {
+        let href = gradient.attribute("href").or(gradient.attribute("xlink:href"))?.strip_prefix('#')?;
+        return if depth < MAX_DEPTH { gradient_color(ids, ids.get(href)?, depth + 1) } else { None };
+    }
+    let count = stops.len() as u32;
+    let channel = |pick: fn(&PaletteColor) -> u8| (stops.iter().map(|color| pick(color) as u32).sum::<u32>() / count) as u8;
+    Some(PaletteColor { r: channel(|color| color.r), g: channel(|color| color.g), b: channel(|color| color.b), a: channel(|color| color.a) })
+}
+
+// #rgb, #rrggbb (with or without alpha), rgb()/rgba() and the basic color names
+fn parse_color(text: &str) -> Option<PaletteColor> {
+    let text = text.trim().to_ascii_lowercase();
+    if let Some(hex) = text.strip_prefix('#') {
+        let digit = |i: usize| u8::from_str_radix(hex.get(i..i + 1)?, 16).ok();
+        let pair = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return match hex.len() {
+            3 | 4 => Some(PaletteColor { r: digit(0)? * 17, g: digit(1)? * 17, b: digit(2)? * 17, a: if hex.len() == 4 { digit(3)? * 17 } else { 255 } }),
+            6 | 8 => Some(PaletteColor { r: pair(0)?, g: pair(2)?, b: pair(4)?, a: if hex.len() == 8 { pair(6)? } else { 255 } }),
+            _ => None,
+        };
+    }
+    if let Some(arguments) = text.strip_prefix("rgba(").or(text.strip_prefix("rgb(")) {
+        let values: Vec<&str> = arguments.trim_end_matches(')').split([',', ' ', '/']).filter(|value| !value.is_empty()).collect();
+        let channel = |value: &str| match value.strip_suffix('%') {
+            Some(percent) => parse_number(percent).map(|percent| (percent * 2.55).clamp(0.0, 255.0) as u8),
+            None => parse_number(value).map(|value| value.clamp(0.0, 255.0) as u8),
+        };
+        let alpha = values.get(3).map_or(Some(255), |alpha| match alpha.strip_suffix('%') {
+            Some(percent) => parse_number(percent).map(|percent| (percent * 2.55).clamp(0.0, 255.0) as u8),
+            None => parse_number(alpha).map(|alpha| (alpha * 255.0).clamp(0.0, 255.0) as u8),
+        })?;
+        return Some(PaletteColor { r: channel(values.first()?)?, g: channel(values.get(1)?)?, b: channel(values.get(2)?)?, a: alpha });
+    }
+    let [r, g, b] = match text.as_str() {
+        "black" => [0, 0, 0],
+        "white" => [255, 255, 255],
+        "red" => [255, 0, 0],
+        "lime" => [0, 255, 0],
+        "green" => [0, 128, 0],
+        "blue" => [0, 0, 255],
+        "yellow" => [255, 255, 0],
+        "cyan" | "aqua" => [0, 255, 255],
+        "magenta" | "fuchsia" => [255, 0, 255],
+        "gray" | "grey" => [128, 128, 128],
+        "silver" => [192, 192, 192],
+        "maroon" => [128, 0, 0],
+        "olive" => [128, 128, 0],
+        "navy" => [0, 0, 128],
+        "purple" => [128, 0, 128],
+        "teal" => [0, 128, 128],
+        "orange" => [255, 165, 0],
+        "transparent" => return Some(PaletteColor::default()),
+        _ => return None,
+    };
+    Some(PaletteColor { r, g, b, a: 255 })
+}
+
+// lengths are all user units in glyph documents, a px suffix changes nothing
+fn parse_number(text: &str) -> Option<f32> {
+    text.trim().trim_end_matches("px").parse().ok()
+}
+
+// just enough xml for svg documents: elements and their attributes. text, comments, the doctype,
+// processing instructions and CDATA are skipped
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    // style="fill:red" wins over fill="red"
+    fn property(&self, name: &str) -> Option<&str> {
+        let from_style = self.attribute("style").and_then(|style| {
+            style.split(';').filter_map(|declaration| declaration.split_once(':')).find(|(key, _)| key.trim() == name).map(|(_, value)| value.trim())
+        });
+        from_style.or_else(|| self.attribute(name))
+    }
+
+    fn number(&self, name: &str) -> f32 {
+        self.attribute(name).and_then(parse_number).unwrap_or(0.0)
+    }
+
+    // every element with an id in the tree, looked up for each <use> and url() so it's worked out once.
+    // the first one in document order wins when ids repeat
+    fn ids(&self) -> Ids<'_> {
+        fn add<'a>(element: &'a Element, ids: &mut Ids<'a>) {
+            if let Some(id) = element.attribute("id") {
+                ids.entry(id).or_insert(element);
+            }
+            element.children.iter().for_each(|child| add(child, ids));
+        }
+        let mut ids = HashMap::new();
+        add(self, &mut ids);
+        ids
+    }
+}
+
+type Ids<'a> = HashMap<&'a str, &'a Element>;
+
+// the returned element is a nameless root holding the document's top level elements.
+// None past MAX_DEPTH levels, walking (and dropping) the tree recurses once per level
+fn parse_xml(text: &str) -> Option<Element> {
+    let new_element = |name: &str| Element { name: name.to_string(), attributes: Vec::new(), children: Vec::new() };
+    let mut stack = vec![new_element("")];
+    let mut rest = text;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = &after[after.find("-->")? + 3..];
+        } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            rest = &after[after.find("]]>")? + 3..];
+        } else if rest.starts_with("<?") || rest.starts_with("<!") {
+            rest = &rest[rest.find('>')? + 1..];
+        } else if let Some(after) = rest.strip_prefix("</") {
+            rest = &after[after.find('>')? + 1..];
+            let element = stack.pop()?;
+            stack.last_mut()?.children.push(element);
+        } else {
+            let after = &rest[1..];
+            let name_end = after.find(|c: char| c.is_whitespace() || c == '>' || c == '/')?;
+            let mut element = new_element(&after[..name_end]);
+            let mut tag = &after[name_end..];
+            loop {
+                tag = tag.trim_start();
+                if let Some(after) = tag.strip_prefix("/>") {
+                    stack.last_mut()?.children.push(element);
+                    rest = after;
+                    break;
+                }
+                if let Some(after) = tag.strip_prefix('>') {
+                    if stack.len() > MAX_DEPTH {
+                        return None;
+                    }
+                    stack.push(element);
+                    rest = after;
+                    break;
+                }
+                let equals = tag.find('=')?;
+                let key = tag[..equals].trim().to_string();
+                tag = tag[equals + 1..].trim_start();
+                let quote = tag.chars().next().filter(|quote| matches!(quote, '"' | '\''))?;
+                let end = tag[1..].find(quote)? + 1;
+                element.attributes.push((key, unescape(&tag[1..end])));
+                tag = &tag[end + 1..];
+            }
+        }
+        if stack.is_empty() {
+            return None; // more closing tags than opening ones
+        }
+    }
+
+    // unclosed elements get closed at the end
+    while stack.len() > 1 {
+        let element = stack.pop()?;
+        stack.last_mut()?.children.push(element);
+    }
+    stack.pop()
+}
+
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
@@ -0,0 +1,325 @@
+use std::fmt;
+
+use crate::font_reader::{FontReader, Tag};
+use crate::font_table_parser::{FontData, CMAP, GLYF, HEAD, LOCA, MAXP, OUTLINE_TABLES, REQUIRED_TABLES};
+
+// https://learn.microsoft.com/en-us/typography/opentype/spec/otff#calculating-checksums
+const CHECKSUM_MAGIC: u32 = 0xB1B0AFBA;
+const HEAD_MAGIC: u32 = 0x5F0F3CF5;
+
+// errors mean the font can't be used safely, warnings are things real fonts get wrong all the time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug)]
+pub struct Issue {
+    pub severity: Severity,
+    pub table: Option<Tag>,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    pub fn errors(&self) -> impl Iterator<Item = &Issue> {
+        self.issues.iter().filter(|issue| issue.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Issue> {
+        self.issues.iter().filter(|issue| issue.severity == Severity::Warning)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    fn error(&mut self, table: Option<Tag>, message: impl Into<String>) {
+        self.issues.push(Issue { severity: Severity::Error, table, message: message.into() });
+    }
+
+    fn warning(&mut self, table: Option<Tag>, message: impl Into<String>) {
+        self.issues.push(Issue { severity: Severity::Warning, table, message: message.into() });
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        match self.table {
+            Some(tag) => write!(f, "{severity} [{tag}] {}", self.message),
+            None => write!(f, "{severity} {}", self.message),
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.issues.is_empty() {
+            return write!(f, "no issues");
+        }
+        for (i, issue) in self.issues.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{issue}")?;
+        }
+        Ok(())
+    }
+}
+
+// sum of the data as big endian u32s, the last one padded with zeros
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+// a glyph that breaks some rule, kept as a count plus the first one so a bad font doesnt produce thousands of lines
+#[derive(Default)]
+struct GlyphCheck {
+    count: usize,
+    first: Option<(usize, String)>,
+}
+
+impl GlyphCheck {
+    fn hit(&mut self, glyph_index: usize, detail: impl FnOnce() -> String) {
+        self.count += 1;
+        if self.first.is_none() {
+            self.first = Some((glyph_index, detail()));
+        }
+    }
+
+    fn report(self, report: &mut ValidationReport, severity: Severity, table: Tag, what: &str) {
+        let Some((glyph_index, detail)) = self.first else { return };
+        let message = match self.count {
+            1 => format!("glyph {glyph_index} {what} ({detail})"),
+            count => format!("{count} glyphs {what}, first is glyph {glyph_index} ({detail})"),
+        };
+        report.issues.push(Issue { severity, table: Some(table), message });
+    }
+}
+
+// limits from a version 1.0 maxp, CFF fonts have a 0.5 maxp with only numGlyphs
+struct MaxpLimits {
+    max_points: u16,
+    max_contours: u16,
+    max_size_of_instructions: u16,
+    max_component_elements: u16,
+}
+
+impl FontData {
+    // a sanitizer pass over the directory and the indices that got read, glyph outlines themselves are
+    // only checked down to their headers so this stays cheap even with lazy glyph parsing
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        self.validate_directory(&mut report);
+        if let Err(err) = self.validate_glyph_data(&mut report) {
+            report.error(None, format!("could not finish checking glyph data: {err}"));
+        }
+        self.validate_cmap(&mut report);
+        report
+    }
+
+    fn validate_directory(&self, report: &mut ValidationReport) {
+        for tag in REQUIRED_TABLES {
+            if !self.font_table.contains_key(&tag) {
+                report.error(Some(tag), "required table is missing");
+            }
+        }
+        for tag in OUTLINE_TABLES {
+            if !self.font_table.contains_key(&tag) && !self.has_bitmaps() {
+                report.error(Some(tag), "required table is missing, and there are no embedded bitmaps to use instead");
+            }
+        }
+
+        let mut records: Vec<_> = self.font_table.iter().collect();
+        records.sort_by_key(|(tag, record)| (record.offset.to_usize(), **tag));
+
+        let file_len = self.data.len();
+        for (tag, record) in records.iter() {
+            let (tag, start, end) = (**tag, record.offset.to_usize(), record.offset.to_usize() + record.length as usize);
+            if end > file_len {
+                report.error(Some(tag), format!("table ends at {end} but the file is only {file_len} bytes"));
+                continue;
+            }
+            if start % 4 != 0 {
+                report.warning(Some(tag), format!("table starts at {start} which is not 4 byte aligned"));
+            }
+
+            let mut sum = checksum(&self.data[start..end]);
+            if tag == HEAD && record.length >= 12 {
+                sum = sum.wrapping_sub(u32::from_be_bytes(self.data[start + 8..start + 12].try_into().unwrap())); // checkSumAdjustment counts as 0
+            }
+            if sum != record.checksum {
+                report.warning(Some(tag), format!("checksum is {sum:#010x} but the directory says {:#010x}", record.checksum));
+            }
+        }
+
+        for pair in records.windows(2) {
+            let ((tag, record), (next_tag, next_record)) = (pair[0], pair[1]);
+            let end = record.offset.to_usize() + record.length as usize;
+            // two tags for the exact same bytes is fine, fonts with both bdat/bloc and EBDT/EBLC do that.
+            // only running into part of another table is broken
+            if record.offset == next_record.offset && record.length == next_record.length {
+                report.warning(Some(*tag), format!("table shares its data with '{next_tag}'"));
+            } else if end > next_record.offset.to_usize() {
+                report.error(Some(*tag), format!("table overlaps '{next_tag}' by {} bytes", end - next_record.offset.to_usize()));
+            }
+        }
+
+        let Ok(mut head) = self.table(HEAD) else { return };
+        let header = head.go_to(8).and_then(|_| Ok((head.read_u32()?, head.read_u32()?)));
+        let Ok((adjustment, magic)) = header else {
+            report.error(Some(HEAD), "table is too short");
+            return;
+        };
+        if magic != HEAD_MAGIC {
+            report.error(Some(HEAD), format!("magic number is {magic:#010x} instead of {HEAD_MAGIC:#010x}"));
+        }
+        // a collection shares tables between its fonts so the whole file sum doesnt mean anything there
+        if !self.data.starts_with(b"ttcf") {
+            let expected = CHECKSUM_MAGIC.wrapping_sub(checksum(&self.data).wrapping_sub(adjustment));
+            if adjustment != expected {
+                report.warning(Some(HEAD), format!("checkSumAdjustment is {adjustment:#010x}, should be {expected:#010x}"));
+            }
+        }
+    }
+
+    fn maxp_limits(&self) -> std::io::Result<Option<MaxpLimits>> {
+        let Ok(mut maxp) = self.table(MAXP) else { return Ok(None) };
+        if maxp.read_fixed()?.0 != 0x00010000 {
+            return Ok(None);
+        }
+        maxp.skip_bytes(2)?; // numGlyphs
+        let max_points = maxp.read_u16()?;
+        let max_contours = maxp.read_u16()?;
+        maxp.skip_bytes(16)?; // composite points/contours, zones, twilight points, storage, function/instruction defs, stack
+        let max_size_of_instructions = maxp.read_u16()?;
+        let max_component_elements = maxp.read_u16()?;
+        Ok(Some(MaxpLimits { max_points, max_contours, max_size_of_instructions, max_component_elements }))
+    }
+
+    fn validate_glyph_data(&self, report: &mut ValidationReport) -> Result<(), Box<dyn std::error::Error>> {
+        let (Ok(glyf), Ok(_)) = (self.table(GLYF), self.table(LOCA)) else { return Ok(()) };
+
+        if self.glyph_locations.len() != self.num_glyphs + 1 {
+            report.error(Some(LOCA), format!("has {} entries, maxp.numGlyphs needs {}", self.glyph_locations.len(), self.num_glyphs + 1));
+        }
+        if let Some(i) = self.glyph_locations.windows(2).position(|pair| pair[1] < pair[0]) {
+            report.error(Some(LOCA), format!("offsets go backwards at glyph {i}"));
+        }
+        if let Some(last) = self.glyph_locations.last().filter(|last| **last > glyf.len()) {
+            report.error(Some(LOCA), format!("points {last} bytes into 'glyf' which is only {} long", glyf.len()));
+        }
+        if report.has_errors() {
+            return Ok(()); // cant trust the glyph ranges
+        }
+
+        let limits = self.maxp_limits()?;
+        let (mut too_many_points, mut too_many_contours, mut too_many_instructions, mut too_many_components) =
+            (GlyphCheck::default(), GlyphCheck::default(), GlyphCheck::default(), GlyphCheck::default());
+        let (mut unordered_ends, mut bad_components, mut truncated) = (GlyphCheck::default(), GlyphCheck::default(), GlyphCheck::default());
+
+        for glyph_index in 0..self.num_glyphs {
+            let start = self.glyph_locations[glyph_index];
+            let mut reader = glyf.sub_reader(start, self.glyph_locations[glyph_index + 1] - start)?;
+            if reader.is_empty() {
+                continue;
+            }
+
+            let header = if reader.read_i16().is_ok_and(|n_contours| n_contours < 0) {
+                reader.go_to(0)?;
+                check_compound_header(&mut reader).map(|components| (components, 0, 0, 0))
+            } else {
+                reader.go_to(0)?;
+                check_simple_header(&mut reader).map(|(contours, points, instructions, ordered)| {
+                    if !ordered {
+                        unordered_ends.hit(glyph_index, || "endPtsOfContours".to_string());
+                    }
+                    (Vec::new(), contours, points, instructions)
+                })
+            };
+            let Ok((components, contours, points, instructions)) = header else {
+                truncated.hit(glyph_index, || format!("{} bytes", reader.len()));
+                continue;
+            };
+
+            if let Some(bad) = components.iter().find(|component| **component >= self.num_glyphs) {
+                bad_components.hit(glyph_index, || format!("component {bad}"));
+            }
+            if let Some(limits) = &limits {
+                if points > limits.max_points as usize {
+                    too_many_points.hit(glyph_index, || format!("{points} > {}", limits.max_points));
+                }
+                if contours > limits.max_contours as usize {
+                    too_many_contours.hit(glyph_index, || format!("{contours} > {}", limits.max_contours));
+                }
+                if instructions > limits.max_size_of_instructions as usize {
+                    too_many_instructions.hit(glyph_index, || format!("{instructions} > {}", limits.max_size_of_instructions));
+                }
+                if components.len() > limits.max_component_elements as usize {
+                    too_many_components.hit(glyph_index, || format!("{} > {}", components.len(), limits.max_component_elements));
+                }
+            }
+        }
+
+        truncated.report(report, Severity::Error, GLYF, "end before their outline does");
+        bad_components.report(report, Severity::Error, GLYF, "use components that dont exist");
+        unordered_ends.report(report, Severity::Error, GLYF, "have contour end points that go backwards");
+        too_many_points.report(report, Severity::Warning, MAXP, "have more points than maxPoints");
+        too_many_contours.report(report, Severity::Warning, MAXP, "have more contours than maxContours");
+        too_many_instructions.report(report, Severity::Warning, MAXP, "have more instructions than maxSizeOfInstructions");
+        too_many_components.report(report, Severity::Warning, MAXP, "have more components than maxComponentElements");
+        Ok(())
+    }
+
+    fn validate_cmap(&self, report: &mut ValidationReport) {
+        let mut out_of_range = self.unicodes_to_index.iter().filter(|(_, glyph_index)| **glyph_index >= self.num_glyphs);
+        if let Some((unicode, glyph_index)) = out_of_range.next() {
+            let count = out_of_range.count() + 1;
+            report.error(Some(CMAP), format!("{count} characters map to glyphs past numGlyphs ({}), U+{unicode:04X} maps to {glyph_index}", self.num_glyphs));
+        }
+    }
+}
+
+// (contours, points, instruction length, end points in order)
+fn check_simple_header(reader: &mut FontReader) -> std::io::Result<(usize, usize, usize, bool)> {
+    let n_contours = reader.read_i16()? as usize;
+    reader.skip_bytes(8)?; // bounding box
+    let mut ends = Vec::with_capacity(n_contours);
+    for _ in 0..n_contours {
+        ends.push(reader.read_u16()?);
+    }
+    let instructions = reader.read_u16()? as usize;
+    reader.skip_bytes(instructions)?;
+    let ordered = ends.windows(2).all(|pair| pair[0] < pair[1]);
+    let points = ends.last().map_or(0, |end| *end as usize + 1);
+    Ok((n_contours, points, instructions, ordered))
+}
+
+// the glyph indices of the components, reads through the whole header so a truncated glyph shows up
+fn check_compound_header(reader: &mut FontReader) -> std::io::Result<Vec<usize>> {
+    reader.skip_bytes(10)?; // numberOfContours and the bounding box
+    let mut components = Vec::new();
+    loop {
+        let flags = reader.read_u16()?;
+        components.push(reader.read_u16()? as usize);
+        let args = if flags & 0x0001 != 0 { 4 } else { 2 };
+        let scale = if flags & 0x0080 != 0 { 8 } else if flags & 0x0040 != 0 { 4 } else if flags & 0x0008 != 0 { 2 } else { 0 };
+        reader.skip_bytes(args + scale)?;
+        if flags & 0x0020 == 0 {
+            return Ok(components);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use crate::font_reader::{FontReader, Tag};
+use crate::font_table_parser::{FontData, FontError, Glyph};
+use crate::os2::Os2;
+
+pub const VHEA: Tag = Tag::new(b"vhea");
+pub const VMTX: Tag = Tag::new(b"vmtx");
+pub const GSUB: Tag = Tag::new(b"GSUB");
+
+// vertical forms of punctuation and brackets, vrt2 is the newer superset of vert
+const VERTICAL_FEATURES: [Tag; 2] = [Tag::new(b"vert"), Tag::new(b"vrt2")];
+const SINGLE_SUBSTITUTION: u16 = 1;
+const EXTENSION_SUBSTITUTION: u16 = 7;
+
+// https://learn.microsoft.com/en-us/typography/opentype/spec/vmtx
+// in font units like glyph_spaces
+#[derive(Clone, Debug, Default)]
+pub struct VerticalMetrics {
+    pub advances: Vec<f32>, // top to bottom
+    pub top_side_bearings: Vec<f32>, // from the vertical origin down to the top of the glyph
+}
+
+impl FontData {
+    // both tables are optional, fonts without them get synthesized metrics (see vertical_advance)
+    pub fn get_vertical_metrics(&mut self) -> Result<(), FontError> {
+        if self.font_table.contains_key(&VHEA) && self.font_table.contains_key(&VMTX) {
+            let mut vhea = self.table(VHEA)?;
+            vhea.go_to(34)?; // numOfLongVerMetrics
+            let num_long_ver_metrics = vhea.read_u16()? as usize;
+            if num_long_ver_metrics == 0 {
+                return Err(FontError::Parse("'vhea' has no vertical metrics".to_string()));
+            }
+
+            let mut vmtx = self.table(VMTX)?;
+            let mut metrics = VerticalMetrics { advances: Vec::with_capacity(self.num_glyphs), top_side_bearings: Vec::with_capacity(self.num_glyphs) };
+            for _ in 0..num_long_ver_metrics.min(self.num_glyphs) {
+                metrics.advances.push(vmtx.read_u16()? as f32);
+                metrics.top_side_bearings.push(vmtx.read_i16()? as f32);
+            }
+
+            // same as hmtx, the glyphs after the long metrics share the last advance and only store a bearing
+            let last_advance = metrics.advances[metrics.advances.len() - 1];
+            for _ in metrics.advances.len()..self.num_glyphs {
+                metrics.advances.push(last_advance);
+                metrics.top_side_bearings.push(vmtx.read_i16().unwrap_or_default() as f32);
+            }
+            self.vertical = Some(metrics);
+        }
+
+        // a broken GSUB only costs the vertical forms, not worth refusing the font over
+        if self.font_table.contains_key(&GSUB) {
+            self.vertical_substitutions = self.read_vertical_substitutions().unwrap_or_default();
+        }
+        Ok(())
+    }
+
+    // font units. without vmtx every glyph is one em box tall, the OS/2 typo ascender to descender
+    pub fn vertical_advance(&self, glyph_index: usize) -> f32 {
+        if let Some(advance) = self.vertical.as_ref().and_then(|metrics| metrics.advances.get(glyph_index)) {
+            return *advance;
+        }
+        match self.os2.as_ref().and_then(Os2::typo_metrics) {
+            Some((ascender, descender, _)) => (ascender as f32 - descender as f32).max(1.0),
+            None => (self.ascent - self.descent) / self.font_scale,
+        }
+    }
+
+    // how far above the glyph's own baseline its vertical origin (the pen, top center of its em box) is,
+    // scaled like the outlines. vmtx gives it as the top side bearing over the glyph's top
+    pub fn vertical_origin(&self, glyph_index: usize, glyph: &Glyph) -> f32 {
+        if let Some(top_side_bearing) = self.vertical.as_ref().and_then(|metrics| metrics.top_side_bearings.get(glyph_index)) {
+            return glyph.bounding_box[3] + top_side_bearing * self.font_scale;
+        }
+        match self.os2.as_ref().and_then(Os2::typo_metrics) {
+            Some((ascender, _, _)) => ascender as f32 * self.font_scale,
+            None => self.ascent,
+        }
+    }
+
+    // the glyph to draw in vertical text, rotated brackets and moved punctuation where the font has them
+    pub fn vertical_glyph(&self, glyph_index: usize) -> usize {
+        self.vertical_substitutions.get(&glyph_index).copied().unwrap_or(glyph_index)
+    }
+
+    // https://learn.microsoft.com/en-us/typography/opentype/spec/gsub
+    // only the single substitutions of the vert/vrt2 features, for every script and language at once
+    fn read_vertical_substitutions(&self) -> Result<HashMap<usize, usize>, FontError> {
+        let gsub = self.table(GSUB)?;
+        let mut header = gsub;
+        header.go_to(6)?; // past the version and the script list
+        let feature_list = gsub.reader_from(header.read_offset16()?.to_usize())?;
+        let lookup_list = gsub.reader_from(header.read_offset16()?.to_usize())?;
+
+        let mut lookup_indices = Vec::new();
+        let mut features = feature_list;
+        for _ in 0..features.read_u16()? {
+            let (tag, offset) = (features.read_tag()?, features.read_offset16()?.to_usize());
+            if !VERTICAL_FEATURES.contains(&tag) {
+                continue;
+            }
+            let mut feature = feature_list.reader_from(offset)?;
+            feature.skip_bytes(2)?; // featureParams
+            for _ in 0..feature.read_u16()? {
+                lookup_indices.push(feature.read_u16()? as usize);
+            }
+        }
+        lookup_indices.sort();
+        lookup_indices.dedup();
+
+        let mut substitutions = HashMap::new();
+        for lookup_index in lookup_indices {
+            let mut lookups = lookup_list;
+            lookups.go_to(2 + lookup_index * 2)?;
+            let lookup = lookup_list.reader_from(lookups.read_offset16()?.to_usize())?;
+            let mut reader = lookup;
+            let lookup_type = reader.read_u16()?;
+            reader.skip_bytes(2)?; // lookupFlag
+            for _ in 0..reader.read_u16()? {
+                let mut subtable = lookup.reader_from(reader.read_offset16()?.to_usize())?;
+                let mut subtable_type = lookup_type;
+                // extensions just point somewhere further away with a 32 bit offset
+                if lookup_type == EXTENSION_SUBSTITUTION {
+                    let mut extension = subtable;
+                    extension.skip_bytes(2)?;
+                    subtable_type = extension.read_u16()?;
+                    subtable = subtable.reader_from(extension.read_offset32()?.to_usize())?;
+                }
+                if subtable_type == SINGLE_SUBSTITUTION {
+                    read_single_substitution(subtable, self.num_glyphs, &mut substitutions)?;
+                }
+            }
+        }
+        substitutions.retain(|from, to| *from < self.num_glyphs && *to < self.num_glyphs);
+        Ok(substitutions)
+    }
+}
+
+fn read_single_substitution(subtable: FontReader, num_glyphs: usize, substitutions: &mut HashMap<usize, usize>) -> std::io::Result<()> {
+    let mut reader = subtable;
+    let format = reader.read_u16()?;
+    let coverage = read_coverage(subtable.reader_from(reader.read_offset16()?.to_usize())?, num_glyphs)?;
+    match format {
+        1 => {
+            let delta = reader.read_i16()?;
+            for glyph in coverage {
+                substitutions.insert(glyph as usize, glyph.wrapping_add(delta as u16) as usize);
+            }
+        }
+        2 => {
+            let count = reader.read_u16()? as usize;
+            for glyph in coverage.into_iter().take(count) {
+                substitutions.insert(glyph as usize, reader.read_u16()? as usize);
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+// the glyphs a subtable applies to, in coverage index order. a real coverage lists every glyph at most once,
+// so ranges stop at the last glyph and there are never more than num_glyphs of them
+fn read_coverage(mut reader: FontReader, num_glyphs: usize) -> std::io::Result<Vec<u16>> {
+    let format = reader.read_u16()?;
+    let count = reader.read_u16()?;
+    let mut glyphs = Vec::new();
+    match format {
+        1 => {
+            for _ in 0..count {
+                glyphs.push(reader.read_u16()?);
+            }
+        }
+        2 => {
+            let last_glyph = num_glyphs.saturating_sub(1).min(u16::MAX as usize) as u16;
+            for _ in 0..count {
+                let (start, end) = (reader.read_u16()?, reader.read_u16()?);
+                reader.skip_bytes(2)?; // startCoverageIndex, the ranges are in order anyway
+                glyphs.extend((start..=end.min(last_glyph)).take(num_glyphs.saturating_sub(glyphs.len())));
+            }
+        }
+        _ => (),
+    }
+    Ok(glyphs)
+}
@@ -0,0 +1,210 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex}
+};
+
+use bevy::{
+    asset::{io::Reader, Asset, AssetLoader, AssetPath, AssetServer, Assets, Handle, LoadContext, RenderAssetUsages},
+    image::{CompressedImageFormats, Image, ImageSampler, ImageType},
+    log::warn,
+    reflect::TypePath,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat}
+};
+use serde::{Deserialize, Serialize};
+
+use font_parser::{
+    bitmap::BitmapImage,
+    color::{PaletteColor, COLR},
+    decoration::ink_in_band,
+    font_table_parser::{FontData, FontError, Glyph},
+    lint::GlyphLint,
+    paint::ColorGlyph,
+    svg::{SvgShape, SVG}
+};
+
+// the parsed font, glyph outlines are parsed the first time render_text asks for them (see FontData::glyph)
+#[derive(Asset, TypePath)]
+pub struct FontAsset {
+    pub data: FontData,
+    pub palettes: Vec<Vec<PaletteColor>>, // CPAL, for the COLR layers of color fonts
+    lints: Mutex<HashMap<usize, Arc<Vec<GlyphLint>>>>, // only looked at in debug mode, worked out once per glyph
+    underline_ink: Mutex<InkCache>, // for underlined frames
+    bitmaps: Mutex<HashMap<(usize, u16), Option<BitmapTexture>>>, // glyph and strike, decoded the first time they get drawn
+    bitmap_placements: Mutex<HashMap<(usize, u16), Option<BitmapPlacement>>>, // glyph and ppem on screen, picking the strike reads the whole directory
+    svg_glyphs: Mutex<HashMap<usize, Option<Arc<Vec<SvgShape>>>>>, // parsing the xml every frame would be way too slow
+    color_paints: Mutex<HashMap<usize, Option<Arc<ColorGlyph>>>>, // same for the COLRv1 paint graphs
+}
+
+// glyph and hinting ppem to the x ranges out of FontAsset::underline_ink
+type InkCache = HashMap<(usize, Option<u16>), Arc<Vec<(f32, f32)>>>;
+
+// an embedded bitmap glyph uploaded as an image, see draw_bitmap_glyphs
+#[derive(Clone)]
+pub struct BitmapTexture {
+    pub image: Handle<Image>,
+    pub colored: bool, // png bitmaps have their own colors, the gray ones get tinted with the text color
+}
+
+// which strike an embedded bitmap comes out of and where it goes, see BitmapGlyph
+#[derive(Clone, Copy)]
+pub struct BitmapPlacement {
+    pub ppem: u16, // of the strike
+    pub pixel_size: f32,
+    pub left: f32,
+    pub bottom: f32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// asset_server.load_with_settings(path, |settings: &mut FontSettings| settings.face_index = 1)
+#[derive(Serialize, Deserialize, Default)]
+pub struct FontSettings {
+    pub face_index: u32, // for .ttc collections
+}
+
+pub fn load_font(asset_server: &AssetServer, path: impl Into<AssetPath<'static>>, face_index: u32) -> Handle<FontAsset> {
+    asset_server.load_with_settings(path, move |settings: &mut FontSettings| settings.face_index = face_index)
+}
+
+impl FontAsset {
+    pub fn parse(bytes: Vec<u8>, face_index: u32) -> Result<Self, FontError> {
+        let data = FontData::parse(bytes, face_index)?;
+        // a broken CPAL only loses the colors, the layers fall back to the text color
+        let palettes = data.palettes().unwrap_or_default();
+        Ok(FontAsset { data, palettes, lints: Mutex::default(), underline_ink: Mutex::default(), bitmaps: Mutex::default(), bitmap_placements: Mutex::default(), svg_glyphs: Mutex::default(), color_paints: Mutex::default() })
+    }
+
+    pub fn lints(&self, glyph_index: usize) -> Arc<Vec<GlyphLint>> {
+        self.lints
+            .lock()
+            .unwrap()
+            .entry(glyph_index)
+            .or_insert_with(|| Arc::new(self.data.lint_glyph(glyph_index).unwrap_or_default()))
+            .clone()
+    }
+
+    // where an underline has to skip over the glyph, in outline units from its origin and with some room on both sides.
+    // glyph is the outline drawn for glyph_index at ppem (hinted or not), the ranges are worked out once for it
+    pub fn underline_ink(&self, glyph_index: usize, ppem: Option<u16>, glyph: &Glyph) -> Arc<Vec<(f32, f32)>> {
+        self.underline_ink
+            .lock()
+            .unwrap()
+            .entry((glyph_index, ppem))
+            .or_insert_with(|| {
+                let metrics = self.data.decoration_metrics();
+                let room = metrics.underline_thickness;
+                let (bottom, top) = (metrics.underline_position - metrics.underline_thickness - room, metrics.underline_position + room);
+                Arc::new(ink_in_band(glyph, bottom, top).into_iter().map(|(start, end)| (start - room, end + room)).collect())
+            })
+            .clone()
+    }
+
+    // None for glyphs without an SVG version, a broken document gets the plain outline too
+    pub fn svg_shapes(&self, glyph_index: usize) -> Option<Arc<Vec<SvgShape>>> {
+        if !self.data.font_table.contains_key(&SVG) {
+            return None;
+        }
+        self.svg_glyphs
+            .lock()
+            .unwrap()
+            .entry(glyph_index)
+            .or_insert_with(|| self.data.svg_glyph(glyph_index).ok().flatten().map(Arc::new))
+            .clone()
+    }
+
+    // None for glyphs without a COLRv1 paint, a broken graph gets the version 0 layers or the plain outline
+    pub fn color_paint(&self, glyph_index: usize) -> Option<Arc<ColorGlyph>> {
+        if !self.data.font_table.contains_key(&COLR) {
+            return None;
+        }
+        self.color_paints
+            .lock()
+            .unwrap()
+            .entry(glyph_index)
+            .or_insert_with(|| self.data.color_paint(glyph_index).ok().flatten().map(Arc::new))
+            .clone()
+    }
+
+    // None for glyphs without a bitmap in any strike, ppem is the size on screen
+    pub fn bitmap_placement(&self, glyph_index: usize, ppem: u16) -> Option<BitmapPlacement> {
+        *self.bitmap_placements
+            .lock()
+            .unwrap()
+            .entry((glyph_index, ppem))
+            .or_insert_with(|| {
+                let bitmap = self.data.bitmap_glyph(glyph_index, ppem as f32).ok().flatten()?;
+                Some(BitmapPlacement {
+                    ppem: bitmap.ppem,
+                    pixel_size: bitmap.pixel_size(),
+                    left: bitmap.left,
+                    bottom: bitmap.bottom,
+                    width: bitmap.width,
+                    height: bitmap.height,
+                })
+            })
+    }
+
+    // None for glyphs the strike doesn't have or whose bitmap won't decode, those are remembered too
+    pub fn bitmap_texture(&self, glyph_index: usize, ppem: u16, images: &mut Assets<Image>) -> Option<BitmapTexture> {
+        self.bitmaps
+            .lock()
+            .unwrap()
+            .entry((glyph_index, ppem))
+            .or_insert_with(|| {
+                let bitmap = self.data.bitmap_glyph(glyph_index, ppem as f32).ok().flatten()?;
+                if bitmap.width == 0 || bitmap.height == 0 {
+                    return None;
+                }
+                let (image, colored) = match bitmap.image {
+                    BitmapImage::Png(png) => {
+                        let image = Image::from_buffer(png, ImageType::Extension("png"), CompressedImageFormats::NONE, true, ImageSampler::Default, RenderAssetUsages::RENDER_WORLD);
+                        (image.ok()?, true)
+                    }
+                    BitmapImage::Gray { .. } => {
+                        // white with the coverage as alpha so the sprite color does the tinting. nearest sampling
+                        // since these are mostly pixel fonts that should stay crisp when scaled up
+                        let pixels = bitmap.coverage()?.into_iter().flat_map(|coverage| [255, 255, 255, coverage]).collect();
+                        let size = Extent3d { width: bitmap.width, height: bitmap.height, depth_or_array_layers: 1 };
+                        let mut image = Image::new(size, TextureDimension::D2, pixels, TextureFormat::Rgba8UnormSrgb, RenderAssetUsages::RENDER_WORLD);
+                        image.sampler = ImageSampler::nearest();
+                        (image, false)
+                    }
+                };
+                Some(BitmapTexture { image: images.add(image), colored })
+            })
+            .clone()
+    }
+}
+
+// loaders run on bevy's task pool so all the parsing happens off the main thread
+#[derive(Default)]
+pub struct FontAssetLoader;
+
+impl AssetLoader for FontAssetLoader {
+    type Asset = FontAsset;
+    type Settings = FontSettings;
+    type Error = FontError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &FontSettings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<FontAsset, FontError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        // a font hot reloaded while it's half written fails the load here and the old one stays around
+        let font = FontAsset::parse(bytes, settings.face_index)?;
+
+        // errors already failed the load, warnings are only worth knowing about
+        for issue in font.data.report.warnings() {
+            warn!("{}: {issue}", load_context.path().display());
+        }
+        Ok(font)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ttf", "otf", "ttc"]
+    }
+}
@@ -1,61 +1,237 @@
 use core::f32;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use bevy::{
-    color::palettes::css::GREEN, 
-    ecs::{query::With, system::Single}, 
+    asset::Handle, 
+    color::{palettes::css::{GREEN, WHITE}, Color}, 
+    ecs::{component::{Component, ComponentId}, entity::Entity, world::DeferredWorld}, 
     gizmos::gizmos::Gizmos, math::Vec2, 
-    render::camera::Camera, 
-    transform::components::GlobalTransform
+    render::{camera::Camera, view::Visibility}, 
+    transform::components::{GlobalTransform, Transform}
 };
+use unicode_segmentation::GraphemeCursor;
+
+use crate::font_asset::FontAsset;
+
+const CARET_BLINK_RATE: f32 = 2.0; // toggles per second
+const SELECTION_COLOR: Color = Color::srgba(0.3, 0.5, 1.0, 0.6);
+const HISTORY_LIMIT: usize = 200; // undo steps kept per frame
+
+// where a char of the frame's text ended up on the last render, the caret needs this to know where to go
+#[derive(Clone, Copy)]
+pub struct GlyphSlot {
+    pub byte_index: usize,
+    pub pen: Vec2, // baseline origin of the char in world space
+    pub advance: f32,
+}
+
+// an embedded bitmap glyph placed by render_text, draw_bitmap_glyphs turns these into sprites
+#[derive(Clone, Copy)]
+pub struct BitmapSlot {
+    pub glyph_index: usize,
+    pub ppem: u16, // the strike that fits the frame's size on screen
+    pub center: Vec2,
+    pub size: Vec2,
+}
+
+// a glyph's index and post name, placed under it by render_text in debug mode, draw_glyph_labels shows them
+#[derive(Clone, Copy)]
+pub struct GlyphLabel {
+    pub glyph_index: usize,
+    pub center: Vec2,
+    pub size: f32, // font size in world units
+}
+
+// lines drawn along the text, placed with the font's post and OS/2 metrics. the whole frame gets them,
+// frames have no styled spans (yet)
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Decorations {
+    pub underline: bool, // skips over descenders
+    pub strikethrough: bool,
+    pub overline: bool,
+}
+
+#[derive(Clone, Copy)]
+pub struct FrameStyle {
+    pub text_color: Color,
+    pub border_color: Option<Color>, // None hides the border
+    pub decorations: Decorations,
+}
+
+impl Default for FrameStyle {
+    fn default() -> Self {
+        Self { text_color: WHITE.into(), border_color: Some(GREEN.into()), decorations: Decorations::default() }
+    }
+}
+
+// what the frame looked like before an edit, undo/redo swaps these back in
+#[derive(Clone)]
+struct EditSnapshot {
+    text: String,
+    caret: usize,
+    selection_anchor: Option<usize>,
+}
+
+// consecutive edits of the same kind (with the caret not moved in between) collapse into one undo step
+#[derive(Clone, Copy, PartialEq)]
+enum EditKind {
+    Typing,
+    Deleting,
+    Other,
+}
 
 // Custom bounded box, with its own text
-#[derive(Default)]
+// locked frames follow the camera and get their Transform written every frame,
+// the rest live in the world wherever their Transform puts them (so they can be moved/parented like anything else)
+#[derive(Component, Default)]
+#[require(Transform, Visibility)]
+#[component(on_add = number_frame)]
 pub struct TextFrame {
     pub name: String,
+    spawn_order: u64, // entity ids get recycled, so tab order and saving go by this instead
     size: Vec2,     // 0 < x,y < 1 (we make the bounding box with this)
-    position: Vec2, // 0 < x,y < 1 (this will be the center of the frame)
+    position: Vec2, // 0 < x,y < 1 (this will be the center of the frame, only used by locked frames)
     
     pub text: String,
+    pub font: Handle<FontAsset>,
+    pub style: FrameStyle,
     pub frame_scale: f32,
     pub locked: bool, // frame remains on screen
+    pub editable: bool, // tab/arrow keys can pick this frame for editing
+    pub vertical: bool, // top to bottom columns going right to left, for CJK
+    pub palette: usize, // which CPAL palette color fonts use, 0 is the font's default
+    pub hinting: bool, // grid fit the outlines at the size they are on screen, see FontData::hinted_glyph
 
     pub t_left: Vec2,
     pub t_right: Vec2,
     pub b_left: Vec2,
     pub b_right: Vec2,
-}
 
-pub trait Frame {
-    fn get_frame_by_name(&mut self, name: String) -> Option<&mut TextFrame>;
+    // editing stuff, caret and selection_anchor are byte indices into text
+    pub caret: usize,
+    pub selection_anchor: Option<usize>,
+    pub editing: bool,
+    pub preedit: String, // text the input method is still composing, drawn at the caret but not part of text yet
+    pub last_edit: f32, // seconds, caret stays solid right after it moves
+    pub layout: Vec<GlyphSlot>, // filled by render_text, last slot is the end of the text
+    pub bitmaps: Vec<BitmapSlot>, // filled by render_text too, for fonts with sbix/CBDT/EBDT bitmaps
+    pub labels: Vec<GlyphLabel>, // same, only in debug mode
+    pub line_height: f32,
+    pub ascent: f32, // above the baseline, in screen units like line_height
+    pub descent: f32, // negative, below the baseline
+
+    undo_stack: Vec<EditSnapshot>,
+    redo_stack: Vec<EditSnapshot>,
+    last_edit_kind: Option<EditKind>,
 }
-impl Frame for Vec<TextFrame> {
-    fn get_frame_by_name(&mut self, name: String) -> Option<&mut TextFrame> {
-       self.iter_mut().find(|frame| frame.name == name)
+
+// counts up for every frame added to an entity
+fn number_frame(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    if let Some(mut frame) = world.get_mut::<TextFrame>(entity) {
+        frame.spawn_order = NEXT.fetch_add(1, Ordering::Relaxed);
     }
 }
 
 impl TextFrame {
+    pub fn spawn_order(&self) -> u64 {
+        self.spawn_order
+    }
+
     pub fn new(name: String, text: String, size: Vec2, position: Vec2, locked: bool, mut frame_scale: Option<f32>) -> Self {
         Self {
             name,
-            text,
             size,
             position,
             locked,
+            editable: true,
             frame_scale: *frame_scale.get_or_insert(size.x),
+            caret: text.len(),
+            text,
             ..Default::default()
         }
     }
 
-    pub fn setup_bounds(
-        mut self,
-        screen_dimensions: Vec2,
-        camera: &Single<(&GlobalTransform, &Camera), With<Camera>>,
-    ) -> Self {
-        self.update(screen_dimensions, camera.0, camera.1);
+    pub fn with_font(mut self, font: Handle<FontAsset>) -> Self {
+        self.font = font;
         self
     }
-    
+
+    pub fn with_style(mut self, style: FrameStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn read_only(mut self) -> Self {
+        self.editable = false;
+        self
+    }
+
+    pub fn vertical(mut self) -> Self {
+        self.vertical = true;
+        self
+    }
+
+    pub fn with_palette(mut self, palette: usize) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    pub fn hinted(mut self) -> Self {
+        self.hinting = true;
+        self
+    }
+
+    // replaces the whole text, caret goes to the end since old indices mean nothing anymore
+    pub fn set_text(&mut self, text: String) {
+        self.caret = text.len();
+        self.selection_anchor = None;
+        self.text = text;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.last_edit_kind = None;
+    }
+
+    fn snapshot(&self) -> EditSnapshot {
+        EditSnapshot { text: self.text.clone(), caret: self.caret, selection_anchor: self.selection_anchor }
+    }
+
+    fn restore(&mut self, snapshot: EditSnapshot) {
+        self.text = snapshot.text;
+        self.caret = snapshot.caret;
+        self.selection_anchor = snapshot.selection_anchor;
+        self.last_edit_kind = None;
+    }
+
+    // call before changing the text
+    fn record(&mut self, kind: EditKind) {
+        self.redo_stack.clear();
+        if kind != EditKind::Other && self.last_edit_kind == Some(kind) {
+            return; // still the same run of typing/deleting
+        }
+        if self.undo_stack.len() == HISTORY_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(self.snapshot());
+        self.last_edit_kind = Some(kind);
+    }
+
+    pub fn undo(&mut self, now: f32) {
+        if let Some(snapshot) = self.undo_stack.pop() {
+            self.redo_stack.push(self.snapshot());
+            self.restore(snapshot);
+            self.last_edit = now;
+        }
+    }
+
+    pub fn redo(&mut self, now: f32) {
+        if let Some(snapshot) = self.redo_stack.pop() {
+            self.undo_stack.push(self.snapshot());
+            self.restore(snapshot);
+            self.last_edit = now;
+        }
+    }
+
     pub fn update(
         &mut self,
         screen_dimensions: Vec2,
@@ -64,7 +240,16 @@ impl TextFrame {
     ) {
         let (s_w, s_h) = (screen_dimensions.x, screen_dimensions.y);
         let center = camera.viewport_to_world_2d(camera_transform,Vec2::new(self.position.x * s_w, self.position.y * s_h)).unwrap();
+        self.place(center, screen_dimensions);
+    }
+
+    pub fn center(&self) -> Vec2 {
+        self.t_left.midpoint(self.b_right)
+    }
 
+    // frames that live in the world get their center from their GlobalTransform
+    pub fn place(&mut self, center: Vec2, screen_dimensions: Vec2) {
+        let (s_w, s_h) = (screen_dimensions.x, screen_dimensions.y);
         let width = s_w * self.size.x * 0.5;
         let height = s_h * self.size.y * 0.5;
 
@@ -79,10 +264,275 @@ impl TextFrame {
         self.b_right = center + b_right;
     }
 
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
     pub fn show(&self, gizmos: &mut Gizmos) {
-        gizmos.line_2d(self.t_left, self.t_right, GREEN);
-        gizmos.line_2d(self.t_left, self.b_left, GREEN);
-        gizmos.line_2d(self.t_right, self.b_right, GREEN);
-        gizmos.line_2d(self.b_left, self.b_right, GREEN);
+        let Some(color) = self.style.border_color else { return };
+        gizmos.line_2d(self.t_left, self.t_right, color);
+        gizmos.line_2d(self.t_left, self.b_left, color);
+        gizmos.line_2d(self.t_right, self.b_right, color);
+        gizmos.line_2d(self.b_left, self.b_right, color);
+    }
+
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        let anchor = self.selection_anchor?;
+        if anchor == self.caret {
+            return None;
+        }
+        Some((anchor.min(self.caret), anchor.max(self.caret)))
+    }
+
+    // moves the caret, extend keeps (or starts) a selection from where the caret was
+    pub fn move_caret(&mut self, to: usize, extend: bool, now: f32) {
+        if extend {
+            if self.selection_anchor.is_none() {
+                self.selection_anchor = Some(self.caret);
+            }
+        } else {
+            self.selection_anchor = None;
+        }
+        self.caret = to.min(self.text.len());
+        self.last_edit = now;
+        self.last_edit_kind = None; // typing somewhere else is a new undo step
+    }
+
+    pub fn select_all(&mut self, now: f32) {
+        self.selection_anchor = Some(0);
+        self.caret = self.text.len();
+        self.last_edit = now;
+        self.last_edit_kind = None;
+    }
+
+    // returns true if there was something to delete
+    pub fn delete_selection(&mut self) -> bool {
+        let Some((start, end)) = self.selection() else {
+            self.selection_anchor = None;
+            return false;
+        };
+        self.text.replace_range(start..end, "");
+        self.caret = start;
+        self.selection_anchor = None;
+        true
+    }
+
+    pub fn insert_str(&mut self, s: &str, now: f32) {
+        self.record(if self.selection().is_some() { EditKind::Other } else { EditKind::Typing });
+        self.delete_selection();
+        self.text.insert_str(self.caret, s);
+        self.caret += s.len();
+        self.last_edit = now;
+    }
+
+    pub fn delete_backward(&mut self, now: f32) {
+        self.last_edit = now;
+        if self.selection().is_some() {
+            self.record(EditKind::Other);
+            self.delete_selection();
+            return;
+        }
+        let start = self.prev_boundary(self.caret);
+        if start == self.caret {
+            return;
+        }
+        self.record(EditKind::Deleting);
+        self.text.replace_range(start..self.caret, "");
+        self.caret = start;
+    }
+
+    pub fn delete_forward(&mut self, now: f32) {
+        self.last_edit = now;
+        if self.selection().is_some() {
+            self.record(EditKind::Other);
+            self.delete_selection();
+            return;
+        }
+        let end = self.next_boundary(self.caret);
+        if end == self.caret {
+            return;
+        }
+        self.record(EditKind::Deleting);
+        self.text.replace_range(self.caret..end, "");
+    }
+
+    // caret steps over whole grapheme clusters (UAX #29) so things like e + combining acute or flags
+    // never get split in half
+    pub fn prev_boundary(&self, from: usize) -> usize {
+        GraphemeCursor::new(from, self.text.len(), true)
+            .prev_boundary(&self.text, 0)
+            .ok()
+            .flatten()
+            .unwrap_or(0)
+    }
+
+    pub fn next_boundary(&self, from: usize) -> usize {
+        GraphemeCursor::new(from, self.text.len(), true)
+            .next_boundary(&self.text, 0)
+            .ok()
+            .flatten()
+            .unwrap_or(from)
+    }
+
+    pub fn is_boundary(&self, at: usize) -> bool {
+        GraphemeCursor::new(at, self.text.len(), true)
+            .is_boundary(&self.text, 0)
+            .unwrap_or(true)
+    }
+
+    // ctrl+left, skip the whitespace behind the caret then the word itself
+    pub fn prev_word_boundary(&self, from: usize) -> usize {
+        let mut i = from;
+        while i > 0 && self.char_before(i).is_some_and(char::is_whitespace) {
+            i = self.prev_boundary(i);
+        }
+        while i > 0 && self.char_before(i).is_some_and(|c| !c.is_whitespace()) {
+            i = self.prev_boundary(i);
+        }
+        i
+    }
+
+    // ctrl+right, skip the rest of the word then the whitespace after it
+    pub fn next_word_boundary(&self, from: usize) -> usize {
+        let mut i = from;
+        while i < self.text.len() && self.char_after(i).is_some_and(|c| !c.is_whitespace()) {
+            i = self.next_boundary(i);
+        }
+        while i < self.text.len() && self.char_after(i).is_some_and(char::is_whitespace) {
+            i = self.next_boundary(i);
+        }
+        i
+    }
+
+    fn char_before(&self, i: usize) -> Option<char> {
+        self.text[..i].chars().next_back()
+    }
+
+    fn char_after(&self, i: usize) -> Option<char> {
+        self.text[i..].chars().next()
+    }
+
+    fn slot_of(&self, byte_index: usize) -> Option<&GlyphSlot> {
+        self.layout.iter().find(|slot| slot.byte_index >= byte_index)
+    }
+
+    // home/end work on the wrapped line the caret is on, not on the raw text
+    pub fn line_start(&self, from: usize) -> usize {
+        let Some(current) = self.slot_of(from) else { return 0 };
+        self.layout
+            .iter()
+            .find(|slot| self.line_position(slot.pen).0 == self.line_position(current.pen).0)
+            .map_or(0, |slot| slot.byte_index)
+    }
+
+    pub fn line_end(&self, from: usize) -> usize {
+        let Some(current) = self.slot_of(from) else { return self.text.len() };
+        let mut on_line = self.layout.iter().filter(|slot| self.line_position(slot.pen).0 == self.line_position(current.pen).0);
+        let last = on_line.next_back().map_or(self.text.len(), |slot| slot.byte_index);
+        // stop before the whitespace the line wrapped on so the caret stays on this line
+        match self.char_after(last) {
+            Some(c) if !c.is_whitespace() => self.next_boundary(last),
+            _ => last,
+        }
+    }
+
+    // where a point sits relative to the lines, (which line, how far along it). lines are rows going
+    // right in horizontal text and columns going down in vertical text
+    fn line_position(&self, point: Vec2) -> (f32, f32) {
+        if self.vertical {
+            (point.x, -point.y)
+        } else {
+            (point.y, point.x)
+        }
+    }
+
+    // closest caret position to a point in world space
+    pub fn caret_from_point(&self, point: Vec2) -> usize {
+        let Some(first) = self.layout.first() else { return self.text.len() };
+        let (point_line, point_along) = self.line_position(point);
+
+        // pick the line whose baseline band (or column) contains the point (or the nearest one)
+        let mut line = self.line_position(first.pen).0;
+        let mut best = f32::MAX;
+        for slot in self.layout.iter() {
+            let slot_line = self.line_position(slot.pen).0;
+            let band_center = if self.vertical { slot_line } else { slot_line + (self.ascent + self.descent) * 0.5 };
+            let distance = (point_line - band_center).abs();
+            if distance < best {
+                best = distance;
+                line = slot_line;
+            }
+        }
+
+        let mut caret = self.text.len();
+        let mut best = f32::MAX;
+        for slot in self.layout.iter().filter(|slot| self.line_position(slot.pen).0 == line && self.is_boundary(slot.byte_index)) {
+            let along = self.line_position(slot.pen).1;
+            let distance = (point_along - along).abs();
+            if distance < best {
+                best = distance;
+                caret = slot.byte_index;
+            }
+            // past the middle of the last char on the line means after it
+            let after = along + slot.advance;
+            if slot.byte_index < self.text.len() && (point_along - after).abs() < best {
+                best = (point_along - after).abs();
+                caret = self.next_boundary(slot.byte_index);
+            }
+        }
+        caret
+    }
+
+    pub fn show_caret(&self, gizmos: &mut Gizmos, now: f32) {
+        if let Some((start, end)) = self.selection() {
+            // one rect per wrapped line
+            let mut line: Option<(f32, f32, f32)> = None; // (line, start, end) like line_position gives them
+            for slot in self.layout.iter().filter(|slot| slot.byte_index >= start && slot.byte_index < end) {
+                let (slot_line, along) = self.line_position(slot.pen);
+                match line {
+                    Some((current, line_start, _)) if current == slot_line => line = Some((current, line_start, along + slot.advance)),
+                    _ => {
+                        if let Some(l) = line {
+                            self.show_selection_line(gizmos, l);
+                        }
+                        line = Some((slot_line, along, along + slot.advance));
+                    }
+                }
+            }
+            if let Some(l) = line {
+                self.show_selection_line(gizmos, l);
+            }
+        }
+
+        let visible = (((now - self.last_edit) * CARET_BLINK_RATE) as u32).is_multiple_of(2);
+        if !visible {
+            return;
+        }
+        if let Some(slot) = self.slot_of(self.caret) {
+            if self.vertical {
+                let half_column = Vec2::new(self.line_height * 0.5, 0.0);
+                gizmos.line_2d(slot.pen - half_column, slot.pen + half_column, self.style.text_color);
+            } else {
+                let bottom = slot.pen + Vec2::new(0.0, self.descent);
+                let top = slot.pen + Vec2::new(0.0, self.ascent);
+                gizmos.line_2d(bottom, top, self.style.text_color);
+            }
+        }
+    }
+
+    fn show_selection_line(&self, gizmos: &mut Gizmos, (line, start, end): (f32, f32, f32)) {
+        if self.vertical {
+            let center = Vec2::new(line, -(start + end) * 0.5);
+            gizmos.rect_2d(center, Vec2::new(self.line_height, end - start), SELECTION_COLOR);
+            return;
+        }
+        let bottom = line + self.descent;
+        let top = line + self.ascent;
+        let center = Vec2::new((start + end) * 0.5, (bottom + top) * 0.5);
+        gizmos.rect_2d(center, Vec2::new(end - start, top - bottom), SELECTION_COLOR);
     }
 }
@@ -1,6 +1,9 @@
-use bevy::{input::mouse::AccumulatedMouseScroll, prelude::*};
+use bevy::{
+    input::{keyboard::{Key, KeyboardInput}, mouse::AccumulatedMouseScroll},
+    prelude::*
+};
 
-use crate::{Frames, frame::Frame, Debug};
+use crate::{CurrentFrame, frame::TextFrame, Debug, Writing};
 
 pub fn zoom_cam(
     mut camera: Single<&mut OrthographicProjection, With<Camera>>,
@@ -17,9 +20,9 @@ pub fn go_to_cursor(
     window: Single<&Window>,
     time: Res<Time>,
     mut looking_at: Local<Vec3>,
-    mut frames: ResMut<Frames>,
+    writing: Res<Writing>,
 ) {
-    if buttons.just_pressed(MouseButton::Left) {
+    if buttons.just_pressed(MouseButton::Left) && !writing.0 {
         let cursor_position = window.cursor_position().expect("could not get cursor pos");
         let wrt_world = camera
             .2
@@ -32,71 +35,186 @@ pub fn go_to_cursor(
         .0
         .translation
         .smooth_nudge(&looking_at, 15., time.delta_secs());
+}
 
-    let global_t = GlobalTransform::from(*camera.0);
-    for frame in frames.0.iter_mut() {
-        if !frame.locked {
-            continue;
+// text comes from the logical key so the keyboard layout and shift are already applied,
+// composed input (dead keys, CJK input methods...) comes through the ime events instead
+fn type_text(
+    frame: &mut TextFrame,
+    keyboard_events: &mut EventReader<KeyboardInput>,
+    ime_events: &mut EventReader<Ime>,
+    ctrl: bool,
+    now: f32,
+) {
+    for event in ime_events.read() {
+        match event {
+            Ime::Preedit { value, .. } => frame.preedit = value.clone(),
+            Ime::Commit { value, .. } => {
+                frame.preedit.clear();
+                frame.insert_str(value, now);
+            }
+            Ime::Disabled { .. } => frame.preedit.clear(),
+            _ => (),
+        }
+    }
+
+    for event in keyboard_events.read() {
+        if !event.state.is_pressed() || ctrl || !frame.preedit.is_empty() {
+            continue; // the input method owns the keys while it is composing
+        }
+
+        match &event.logical_key {
+            Key::Character(character) if !character.chars().any(char::is_control) => frame.insert_str(character, now),
+            Key::Space => frame.insert_str(" ", now),
+            Key::Enter => frame.insert_str("\n", now),
+            Key::Backspace => frame.delete_backward(now),
+            Key::Delete => frame.delete_forward(now),
+            _ => (),
         }
-        frame.update(window.size(), &global_t, camera.2); // cant use camera.1 instead of global_t because it isnt updated till PostUpdate
     }
 }
 
-fn keycode_to_string(key: &KeyCode) -> String {
-    let debug = format!("{:?}", key);
-    if let Some(s) = debug.strip_prefix("Key") {
-        s.to_string()
-    } else if let Some(s) = debug.strip_prefix("Digit") {
-        s.to_string()
+// arrows, home/end and ctrl+arrow word jumps, holding shift selects
+fn move_caret(frame: &mut TextFrame, keyboard_input: &ButtonInput<KeyCode>, now: f32) -> bool {
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let ctrl = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+
+    let to = if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        match frame.selection() {
+            Some((start, _)) if !shift && !ctrl => start, // collapse the selection instead of moving
+            _ if ctrl => frame.prev_word_boundary(frame.caret),
+            _ => frame.prev_boundary(frame.caret),
+        }
+    } else if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        match frame.selection() {
+            Some((_, end)) if !shift && !ctrl => end,
+            _ if ctrl => frame.next_word_boundary(frame.caret),
+            _ => frame.next_boundary(frame.caret),
+        }
+    } else if keyboard_input.just_pressed(KeyCode::Home) {
+        if ctrl { 0 } else { frame.line_start(frame.caret) }
+    } else if keyboard_input.just_pressed(KeyCode::End) {
+        if ctrl { frame.text.len() } else { frame.line_end(frame.caret) }
     } else {
-        String::from("skip")
+        return false;
+    };
+
+    frame.move_caret(to, shift, now);
+    true
+}
+
+// click places the caret, dragging (or shift+click) selects
+fn mouse_caret(
+    frame: &mut TextFrame,
+    buttons: &ButtonInput<MouseButton>,
+    keyboard_input: &ButtonInput<KeyCode>,
+    window: &Window,
+    camera: &(&GlobalTransform, &Camera),
+    now: f32,
+) {
+    if !buttons.pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(point) = window
+        .cursor_position()
+        .and_then(|cursor| camera.1.viewport_to_world_2d(camera.0, cursor).ok())
+    else {
+        return;
+    };
+
+    let to = frame.caret_from_point(point);
+    if buttons.just_pressed(MouseButton::Left) {
+        let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        frame.move_caret(to, shift, now);
+    } else if to != frame.caret {
+        frame.move_caret(to, true, now);
     }
 }
 
+// next editable frame after the current one, in spawn order
+fn next_frame(frames: &Query<(Entity, &mut TextFrame)>, current: Option<Entity>) -> Option<Entity> {
+    let mut editable: Vec<(Entity, &TextFrame)> = frames.iter().filter(|(_, frame)| frame.editable).collect();
+    editable.sort_by_key(|(_, frame)| frame.spawn_order());
+    let editable: Vec<Entity> = editable.into_iter().map(|(entity, _)| entity).collect();
+    let after = current.and_then(|current| editable.iter().position(|entity| *entity == current)).map_or(0, |i| i + 1);
+    editable.get(after).or(editable.first()).copied()
+}
+
+#[allow(clippy::too_many_arguments)] // bevy system
 pub fn input_stuff(
     mut debug: ResMut<Debug>, 
-    mut writing: Local<bool>, 
+    mut writing: ResMut<Writing>, 
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut ime_events: EventReader<Ime>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut window: Single<&mut Window>,
+    camera: Single<(&GlobalTransform, &Camera), With<Camera>>,
+    time: Res<Time>,
 
-    mut frames: ResMut<Frames>,
-    mut current_frame_name: Local<String>,
-    mut frame_index: Local<usize>,
+    mut frames: Query<(Entity, &mut TextFrame)>,
+    mut current_frame: ResMut<CurrentFrame>,
 ) {    
-    if *frame_index == 0 {
-        *frame_index = 2; // ignore fps and current_frame_display
-        *current_frame_name = frames.0[*frame_index].name.clone();
-    } else {
-        let c_frame_display = frames.0.get_frame_by_name(String::from("current_frame")).ok_or_else(|| format!("Current Frame Display not found!")).unwrap();
-        if c_frame_display.text != *current_frame_name { 
-            c_frame_display.text = current_frame_name.clone();
-        }
+    if current_frame.0.is_none_or(|entity| !frames.contains(entity)) {
+        current_frame.0 = next_frame(&frames, None); // nothing picked yet or it got despawned
     }
+    let Some(current_entity) = current_frame.0 else {
+        keyboard_events.clear();
+        ime_events.clear();
+        return;
+    };
     
-    let current_frame = &mut frames.0[*frame_index];
+    let now = time.elapsed_secs();
+    // AltGr shows up as ControlLeft + AltRight on Windows and is for typing @, € and such, so ctrl with
+    // an alt key held isn't a shortcut
+    let ctrl = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+        && !keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    let (_, mut current) = frames.get_mut(current_entity).unwrap();
+    let current_frame_text = &mut *current;
+    current_frame_text.editing = writing.0;
+    if writing.0 {
+        mouse_caret(current_frame_text, &buttons, &keyboard_input, &window, &camera, now);
+        type_text(current_frame_text, &mut keyboard_events, &mut ime_events, ctrl, now);
+
+        // keeps the input method's candidate window next to the caret
+        if let Some(caret) = current_frame_text.layout.iter().find(|slot| slot.byte_index >= current_frame_text.caret)
+            && let Ok(position) = camera.1.world_to_viewport(camera.0, caret.pen.extend(0.0))
+            && window.ime_position != position
+        {
+            window.ime_position = position;
+        }
+    } else {
+        keyboard_events.clear();
+        ime_events.clear();
+    }
+    if window.ime_enabled != writing.0 {
+        window.ime_enabled = writing.0;
+    }
+
     if keyboard_input.just_pressed(KeyCode::CapsLock) {
         debug.0 = !debug.0;
     } else if keyboard_input.just_pressed(KeyCode::Tab) {
-        *writing = !*writing;
-    } else if keyboard_input.just_pressed(KeyCode::ArrowRight) {
-        *frame_index += 1;
-        if *frame_index == frames.0.len() {
-            *frame_index = 2; // // ignore fps and current_frame_display
-        }
-        *current_frame_name = frames.0[*frame_index].name.clone();
-    } else if *writing && keyboard_input.just_pressed(KeyCode::Backspace) && !current_frame.text.is_empty() {
-        current_frame.text.pop();
-    } else if *writing && keyboard_input.just_pressed(KeyCode::Space) {
-        current_frame.text.push(' ');
-    } else if *writing && keyboard_input.pressed(KeyCode::ShiftRight) { // funny
-        current_frame.text.push('E');
-    } else if *writing {
-        let just_pressed = keyboard_input.get_just_pressed();
-        let holding_shift = keyboard_input.pressed(KeyCode::ShiftLeft);
-        for key in just_pressed.into_iter() {
-            let s = keycode_to_string(key);
-            if s == "skip" { continue }
-            let cased = if holding_shift {&s} else {&(s.to_lowercase())};
-            current_frame.text.push_str(cased);
+        writing.0 = !writing.0;
+        current_frame_text.editing = writing.0;
+        current_frame_text.selection_anchor = None;
+        current_frame_text.preedit.clear();
+        current_frame_text.last_edit = now;
+    } else if !writing.0 && keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        current_frame_text.editing = false;
+        current_frame.0 = next_frame(&frames, Some(current_entity));
+    } else if !writing.0 && keyboard_input.just_pressed(KeyCode::KeyH) {
+        current_frame_text.hinting = !current_frame_text.hinting;
+    } else if writing.0 && move_caret(current_frame_text, &keyboard_input, now) {
+        // caret already moved
+    } else if writing.0 && ctrl && keyboard_input.just_pressed(KeyCode::KeyA) {
+        current_frame_text.select_all(now);
+    } else if writing.0 && ctrl && keyboard_input.just_pressed(KeyCode::KeyZ) {
+        if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            current_frame_text.redo(now);
+        } else {
+            current_frame_text.undo(now);
         }
+    } else if writing.0 && ctrl && keyboard_input.just_pressed(KeyCode::KeyY) {
+        current_frame_text.redo(now);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,19 @@
+// the parser lives in its own bevy-free crate (font-parser), everything else here is the bevy side
+// and needs the viewer feature (on by default)
+pub use font_parser::{font_reader, font_table_parser, geometry};
+
+#[cfg(feature = "viewer")]
+pub mod font_asset;
+#[cfg(feature = "viewer")]
+pub mod frame;
+#[cfg(feature = "viewer")]
+pub mod input_handle;
+#[cfg(feature = "viewer")]
+pub mod renderer;
+#[cfg(feature = "viewer")]
+pub mod scene;
+#[cfg(feature = "viewer")]
+mod plugin;
+
+#[cfg(feature = "viewer")]
+pub use plugin::*;
@@ -1,162 +1,296 @@
-mod font_reader;
-mod font_table_parser;
-mod frame;
-mod input_handle;
-mod renderer;
+use std::path::PathBuf;
 
-use frame::TextFrame;
-use core::f32;
-use std::collections::HashMap;
-
-use font_reader::FontReader;
-use font_table_parser::{FontData, Glyph};
-use renderer::render_text;
+use text_renderer::{
+    font_asset::FontAsset,
+    font_table_parser::{FontData, FONT_SIZE_CONSTANT},
+    frame::TextFrame,
+    input_handle::{go_to_cursor, zoom_cam},
+    scene::{FrameDescription, FrameScene, StyleDescription},
+    CurrentFrame,
+    Debug,
+    TextRendererPlugin,
+    update_frames
+};
 
 use bevy::{
-    diagnostic::FrameTimeDiagnosticsPlugin, 
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin}, 
     prelude::*
 };
+use clap::Parser;
 
-use crate::input_handle::{go_to_cursor, zoom_cam, input_stuff};
+/// TrueType font viewer
+#[derive(Parser, Resource)]
+#[command(version, about)]
+struct Args {
+    /// Font file to use instead of the frames file's font, can be given more than once.
+    /// With --text every font gets its own frame
+    #[arg(long = "font", value_name = "PATH")]
+    fonts: Vec<PathBuf>,
 
-#[derive(Resource)]
-struct GlyphData(Vec<Glyph>);
+    /// Which font to use out of a .ttc collection (applies to the first font)
+    #[arg(long, default_value_t = 0)]
+    face: u32,
 
-#[derive(Resource)]
-struct GlyphUnicode(HashMap<u32, usize>);
+    /// Text to show, replaces the editable frames of the frames file
+    #[arg(long)]
+    text: Option<String>,
 
-#[derive(Resource)]
-struct GlyphSpaces(Vec<f32>);
+    /// Same as --text but read from a file
+    #[arg(long, value_name = "PATH", conflicts_with = "text")]
+    text_file: Option<PathBuf>,
 
-#[derive(Resource)]
-struct FontScaleANDLineHeight(f32, f32);
+    /// Set the --text frames vertically, top to bottom columns going right to left
+    #[arg(long)]
+    vertical: bool,
 
-#[derive(Resource)]
-struct Frames(Vec<TextFrame>);
+    /// Color palette for color (COLR) fonts in the --text frames, 0 is the font's default
+    #[arg(long, default_value_t = 0)]
+    palette: usize,
 
-#[derive(Resource)]
-struct Debug(bool); // RED MEANS ONCURVE; GREEN MEANS OFFCURVE; BLUE MEANS IMPLIED POINT
+    /// Grid fit the editable frames' outlines with the font's TrueType instructions (H toggles it per frame)
+    #[arg(long)]
+    hinting: bool,
+
+    /// Font size of the editable frames in pixels
+    #[arg(long)]
+    size: Option<f32>,
+
+    #[arg(long, default_value_t = 1280.0)]
+    width: f32,
+
+    #[arg(long, default_value_t = 720.0)]
+    height: f32,
+
+    /// Starting camera zoom, smaller is closer (0.15 to 2.0)
+    #[arg(long, default_value_t = 1.0)]
+    zoom: f32,
+
+    /// Start with debug mode (contour points) on
+    #[arg(long)]
+    debug: bool,
+
+    /// Language for the font names in the debug info frame (like de or pt-BR), falls back to english
+    #[arg(long, default_value = "en")]
+    language: String,
+
+    /// Validate the fonts given with --font, print what is wrong with them and exit
+    #[arg(long)]
+    check: bool,
+
+    /// Print the outline lints (odd contours, wrong direction, self intersections...) of every glyph
+    /// in the fonts given with --font and exit
+    #[arg(long, conflicts_with = "check")]
+    lint: bool,
+
+    /// Frames file to start from, ctrl+s saves back to it
+    #[arg(long, value_name = "PATH", default_value = "assets/frames.ron")]
+    frames: PathBuf,
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    if args.check || args.lint {
+        return check_fonts(&args);
+    }
+
+    let text = match &args.text_file {
+        Some(path) => Some(std::fs::read_to_string(path)?),
+        None => args.text.clone(),
+    };
+    let mut scene = FrameScene::load(&args.frames)?;
+    apply_args(&mut scene, &args, text)?;
+
     App::new()
-        .add_plugins((DefaultPlugins, FrameTimeDiagnosticsPlugin::default()))
-        .add_systems(Startup, (setup_window, load_assets).chain())
-        .add_systems(Update, (go_to_cursor, zoom_cam, render_text, input_stuff).chain())
+        .add_plugins((
+            DefaultPlugins.set(WindowPlugin {
+                primary_window: Some(Window {
+                    resolution: (args.width, args.height).into(),
+                    ..default()
+                }),
+                ..default()
+            }),
+            FrameTimeDiagnosticsPlugin,
+            TextRendererPlugin,
+        ))
+        .add_systems(Startup, (setup_window, load_assets, setup_frames).chain())
+        .add_systems(Update, ((go_to_cursor, zoom_cam).before(update_frames), show_fps, show_current_frame, show_font_info, save_frames))
         .insert_resource(ClearColor(Color::BLACK))
-        .insert_resource(Debug(false))
-        .insert_resource(Frames(Vec::new()))
+        .insert_resource(Debug(args.debug))
+        .insert_resource(StartingScene(Some(scene)))
+        .insert_resource(args)
         .run();
 
     Ok(())
 }
 
-fn setup_implied_points(glyph_data: &mut Vec<Glyph>) {
-    for glyph in glyph_data.iter_mut() {
-        let (glyph_coords, contour_end_points) = (&glyph.coordinates, &glyph.contour_end_pts);
-        
-        let mut contour_start = 0;
-        for contour_end in contour_end_points.iter() {
-            /*
-            first we loop over the points in the contour and if two consecutive points are oncurve or offcurve, we insert
-            an implied offcurve or oncurve point which will help us control the bezier curve
-            */
-
-            /*
-            found this bug with JETBRAINS MONO at the moment
-            BUG: some glyphs like Ŀ ŀ, their dot contour is just a single point, not a set of points so gotta hardcode a dot i guess
-            well thats what i hope so and its not a parsing issue.
-
-            therefore im usize:min(ing) contour_end for now because it leads to index error
-            */
-            let contour_end = usize::min(*contour_end as usize + 1, glyph_coords.len()); 
-            
-            let old_contour = &glyph_coords[contour_start..(contour_end)];
-            let oc_size = old_contour.len();
-
-            let mut first_oncurve_offset = 0; // sometimes the first point isnt on_curve
-            while first_oncurve_offset < oc_size {
-                if old_contour[first_oncurve_offset].1 {
-                    break;
-                }
-                first_oncurve_offset += 1;
+// --check and --lint, both only look at the fonts and never open a window
+fn check_fonts(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let mut failed = 0;
+    for path in args.fonts.iter() {
+        match FontData::open(path, args.face) {
+            Ok(font) if args.lint => println!("{}:\n{}", path.display(), font.lint_report()),
+            Ok(font) => println!("{}: ok\n{}", path.display(), font.report),
+            Err(err) => {
+                failed += 1;
+                println!("{}: {err}", path.display());
             }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{failed} of {} fonts failed", args.fonts.len()).into());
+    }
+    Ok(())
+}
 
-            let mut contour_with_implied_points: Vec<(Vec2, u8)> = Vec::with_capacity(oc_size);
+#[derive(Resource)]
+struct StartingScene(Option<FrameScene>);
 
-            let mut i = 0;
-            while i < oc_size {
-                let a = old_contour[(i + first_oncurve_offset) % oc_size];
-                let b = old_contour[(i + first_oncurve_offset + 1) % oc_size];
+#[derive(Resource)]
+struct SceneFont(String, u32); // the frames file's default font and face, saved back as is
 
-                contour_with_implied_points.push((a.0, if a.1 {0} else {1})); // 0 MEANS ONCURVE 1 MEANS OFFCURVE
-                if a.1 == b.1 {
-                    // both points either on or off curve, then we insert a midpoint as a control point for bezier
-                    contour_with_implied_points.push((a.0.midpoint(b.0),2)); // 2 means INSERTED POINT
-                }
-                i += 1;
-            }
+// fonts given on the command line are relative to where we were started from, not to the assets folder
+fn font_path(path: &PathBuf) -> std::io::Result<String> {
+    Ok(std::path::absolute(path)?.to_string_lossy().into_owned())
+}
+
+fn apply_args(scene: &mut FrameScene, args: &Args, text: Option<String>) -> std::io::Result<()> {
+    let fonts = args.fonts.iter().map(font_path).collect::<std::io::Result<Vec<_>>>()?;
+    if let Some(first) = fonts.first() {
+        scene.font = first.clone();
+        scene.face_index = args.face;
+        scene.frames.iter_mut().for_each(|frame| frame.font = None);
+    }
+
+    if let Some(text) = text {
+        // one locked frame per font, stacked under each other
+        scene.frames.retain(|frame| !frame.editable);
+        let count = fonts.len().max(1);
+        let height = 0.9 / count as f32;
+        for i in 0..count {
+            let font = fonts.get(i).filter(|_| i > 0).cloned(); // the first one is the scene font already
+            let name = args.fonts.get(i).and_then(|path| path.file_name()).map_or("text".to_string(), |name| name.to_string_lossy().into_owned());
+            scene.frames.push(FrameDescription {
+                name,
+                text: text.clone(),
+                size: (1.0, height),
+                position: (0.5, 0.05 + height * (i as f32 + 0.5)),
+                locked: true,
+                scale: Some(0.5),
+                font,
+                editable: true,
+                style: StyleDescription::default(),
+                vertical: args.vertical,
+                palette: args.palette,
+                hinting: false, // set with the other editable frames below
+            });
+        }
+    }
 
-            glyph.contour_coordinates.push(contour_with_implied_points);
-            contour_start = contour_end;
+    if let Some(size) = args.size {
+        for frame in scene.frames.iter_mut().filter(|frame| frame.editable) {
+            frame.scale = Some(size / FONT_SIZE_CONSTANT);
         }
-        glyph.coordinates = Vec::new();
-        glyph.contour_end_pts = Vec::new(); 
-        // they are dead weight
     }
+    if args.hinting {
+        scene.frames.iter_mut().filter(|frame| frame.editable).for_each(|frame| frame.hinting = true);
+    }
+    Ok(())
 }
 
-fn load_assets(mut commands: Commands) {
-    commands.spawn(Camera2d);
+fn load_assets(mut commands: Commands, args: Res<Args>) {
+    commands.spawn((
+        Camera2d,
+        OrthographicProjection {
+            scale: args.zoom.clamp(0.15, 2.0), // same limits as zoom_cam
+            ..OrthographicProjection::default_2d()
+        },
+    ));
+}
+
+fn setup_frames(mut commands: Commands, asset_server: Res<AssetServer>, mut starting_scene: ResMut<StartingScene>) {
+    let scene = starting_scene.0.take().expect("frames were already set up");
+    scene.spawn(&mut commands, &asset_server);
+    commands.insert_resource(SceneFont(scene.font, scene.face_index));
+}
 
-    let reader = FontReader::new("fonts/using.ttf").unwrap();
-    let mut font_data_parser = FontData {
-        reader,
-        ..default()
+fn save_frames(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    frames: Query<(&TextFrame, &Transform)>,
+    asset_server: Res<AssetServer>,
+    scene_font: Res<SceneFont>,
+    args: Res<Args>,
+) {
+    let ctrl = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !keyboard_input.just_pressed(KeyCode::KeyS) {
+        return;
+    }
+
+    let mut frames: Vec<_> = frames.iter().collect();
+    frames.sort_by_key(|(frame, _)| frame.spawn_order()); // keep them in the order they were spawned
+    let scene = FrameScene::from_frames(frames, &asset_server, scene_font.0.clone(), scene_font.1);
+    match scene.save(&args.frames) {
+        Ok(()) => info!("saved frames to {}", args.frames.display()),
+        Err(err) => error!("{}: {err}", args.frames.display()),
+    }
+}
+
+fn show_fps(diagnostics: Res<DiagnosticsStore>, mut frames: Query<&mut TextFrame>) {
+    let Some(fps) = diagnostics.get(&FrameTimeDiagnosticsPlugin::FPS).and_then(|fps| fps.smoothed()) else {
+        return;
     };
+    // a saved scene doesn't have to keep the fps frame around
+    let Some(mut fps_frame) = frames.iter_mut().find(|frame| frame.name == "fps") else {
+        return;
+    };
+    fps_frame.set_text(format!("FPS: {:.0}", fps));
+}
 
-    font_data_parser.get_lookup_table().unwrap();
-    font_data_parser.get_glyph_location().unwrap();
-    font_data_parser.get_glyphs().unwrap();
-    font_data_parser.map_glyph_to_unicode().unwrap();
-    font_data_parser.get_glyph_spacings().unwrap();
-    setup_implied_points(&mut font_data_parser.glyphs);
-    
-    commands.insert_resource(FontScaleANDLineHeight(font_data_parser.font_scale, font_data_parser.line_height));
-    commands.insert_resource(GlyphData(font_data_parser.glyphs));
-    commands.insert_resource(GlyphUnicode(font_data_parser.unicodes_to_index));
-    commands.insert_resource(GlyphSpaces(font_data_parser.glyph_spaces));
+fn show_current_frame(current_frame: Res<CurrentFrame>, mut frames: Query<&mut TextFrame>) {
+    let Some(name) = current_frame.0.and_then(|entity| frames.get(entity).ok()).map(|frame| frame.name.clone()) else {
+        return;
+    };
+    let Some(mut c_frame_display) = frames.iter_mut().find(|frame| frame.name == "current_frame") else {
+        return;
+    };
+    if c_frame_display.text != name {
+        c_frame_display.set_text(name);
+    }
 }
 
-fn setup_frames(
-    camera: Single<(&GlobalTransform, &Camera), With<Camera>>, 
-    mut frames: ResMut<Frames>,
-    window: Single<&Window>,
+// the names out of the current frame's font, only while debug mode is on
+fn show_font_info(
+    debug: Res<Debug>,
+    current_frame: Res<CurrentFrame>,
+    fonts: Res<Assets<FontAsset>>,
+    args: Res<Args>,
+    mut frames: Query<(&mut TextFrame, &mut Visibility)>,
 ) {
-    let screen_dimensions = window.size();
-    let fps_display = TextFrame::new("fps".to_string(),"FPS: 212".to_string(), Vec2::new(0.1,0.05), Vec2::new(0.05,0.025), true, Some(0.3)).setup_bounds(screen_dimensions, &camera);
-    let current_frame_display = TextFrame::new("current_frame".to_string(),"big chungus is big hot".to_string(), Vec2::new(0.2,0.05), Vec2::new(0.9,0.025), true, Some(0.3)).setup_bounds(screen_dimensions, &camera);
-    let screen = TextFrame::new("screen".to_string(),"The naïve Noël café-owner’s façade was façade-ish; he créped his crêpes with brio while his learnèd, résumé-wielding pâtissier, Zoë, façaded a façade in the Hôtel de Ville.".to_string(), Vec2::new(1.0,0.9), Vec2::new(0.5,0.5), true, Some(0.5)).setup_bounds(screen_dimensions, &camera);
-    let m = TextFrame::new("m".to_string(),"big money".to_string(), Vec2::new(0.6,0.3), Vec2::new(0.6,0.8), false, None).setup_bounds(screen_dimensions, &camera);
-    frames.0.push(fps_display);
-    frames.0.push(current_frame_display);
-    frames.0.push(screen);
-    frames.0.push(m)
+    // the current frame's font and the character right after its caret
+    let current = current_frame.0.and_then(|entity| frames.get(entity).ok()).map(|(frame, _)| (frame.font.id(), frame.text[frame.caret..].chars().next()));
+    let Some((mut info_frame, mut visibility)) = frames.iter_mut().find(|(frame, _)| frame.name == "font_info") else {
+        return;
+    };
+    visibility.set_if_neq(if debug.0 { Visibility::Inherited } else { Visibility::Hidden });
+    if !debug.0 {
+        return;
+    }
+
+    let Some((font, char)) = current.and_then(|(id, char)| Some((fonts.get(id)?, char))) else { return };
+    let mut info = font.data.metadata(&args.language).to_string();
+    if let Some(os2) = &font.data.os2 {
+        let style = if os2.is_italic() { "italic" } else { "upright" };
+        info += &format!("\nweight {}, width {}, {style}", os2.weight_class, os2.width_class);
+    }
+    if let Some(char) = char {
+        let glyph_index = font.data.unicodes_to_index.get(&(char as u32)).copied().unwrap_or(0);
+        info += &format!("\nglyph {glyph_index} {} (U+{:04X})", font.data.glyph_name(glyph_index).unwrap_or("(no name)"), char as u32);
+    }
+    if info_frame.text != info {
+        info_frame.set_text(info);
+    }
 }
 
 fn setup_window(mut window: Single<&mut Window>) {
     window.title = String::from("Text Rendering");
     window.position = WindowPosition::Centered(MonitorSelection::Current);
 }
-
-// fn on_window_resize(
-//     mut resize_events: EventReader<bevy::window::WindowResized>,
-//     mut frames: ResMut<Frames>,
-//     camera: Single<(&GlobalTransform, &Camera), With<Camera>>,
-//     window: Single<&Window>,
-// ) {
-//     if resize_events.read().len() != 0 {
-//         for frame in frames.0.iter_mut() {
-//             frame.update(window.size(), camera.0, camera.1);
-//         }
-//     }
-// }
\ No newline at end of file
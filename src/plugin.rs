@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::AssetLoadFailedEvent,
+    prelude::*,
+    render::camera::CameraUpdateSystem,
+    transform::{helper::TransformHelper, TransformSystem},
+    window::{WindowResized, WindowScaleFactorChanged}
+};
+
+use crate::{
+    font_asset::{FontAsset, FontAssetLoader},
+    frame::TextFrame,
+    input_handle::input_stuff,
+    renderer::{draw_bitmap_glyphs, draw_glyph_labels, render_text}
+};
+
+#[derive(Resource)]
+pub struct Debug(pub bool); // RED MEANS ONCURVE; GREEN MEANS OFFCURVE; BLUE MEANS IMPLIED POINT; FUCHSIA RINGS ARE GLYPH LINTS
+
+#[derive(Resource)]
+pub struct Writing(pub bool); // clicks place the caret instead of moving the camera while this is on
+
+#[derive(Resource, Default)]
+pub struct CurrentFrame(pub Option<Entity>); // the frame tab/typing goes to
+
+// spawn entities with a TextFrame and they get laid out, drawn and can be edited.
+// fonts are loaded through the AssetServer as FontAsset, a frame shows up once its font is ready
+pub struct TextRendererPlugin;
+
+impl Plugin for TextRendererPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<FontAsset>()
+            .init_asset_loader::<FontAssetLoader>()
+            .insert_resource(Debug(false))
+            .insert_resource(Writing(false))
+            .init_resource::<CurrentFrame>()
+            .add_systems(Update, (on_font_changed, update_frames, render_text, draw_bitmap_glyphs, draw_glyph_labels, input_stuff).chain())
+            .add_systems(PostUpdate, on_window_resize.after(CameraUpdateSystem).after(TransformSystem::TransformPropagate));
+    }
+}
+
+// with the file_watcher feature bevy reloads a font behind the same handle when its file changes,
+// frames keep their text and caret and just get laid out with the new glyphs on the next render
+fn on_font_changed(
+    mut font_events: EventReader<AssetEvent<FontAsset>>,
+    mut failed_events: EventReader<AssetLoadFailedEvent<FontAsset>>,
+    frames: Query<&TextFrame>,
+) {
+    for event in font_events.read() {
+        let AssetEvent::Modified { id } = event else { continue };
+        let relaid = frames.iter().filter(|frame| frame.font.id() == *id).count();
+        info!("font {id} reloaded, re-laying out {relaid} frames");
+    }
+
+    for event in failed_events.read() {
+        warn!("could not load font {}, keeping the previous one if there is one: {}", event.path, event.error);
+    }
+}
+
+#[allow(clippy::type_complexity)] // bevy system
+pub fn update_frames(
+    camera: Single<(&Transform, &Camera), With<Camera>>,
+    window: Single<&Window>,
+    mut frames: ParamSet<(Query<(Entity, &mut TextFrame, &mut Transform, Option<&Parent>), Without<Camera>>, TransformHelper)>,
+) {
+    let camera_transform = GlobalTransform::from(*camera.0); // cant use the cameras GlobalTransform because it isnt updated till PostUpdate
+
+    // same goes for the frames, so unlocked ones get theirs and locked ones their parent's worked out from the hierarchy
+    let wanted: Vec<(Entity, Option<Entity>)> = frames
+        .p0()
+        .iter()
+        .map(|(entity, frame, _, parent)| (entity, if frame.locked { parent.map(Parent::get) } else { Some(entity) }))
+        .collect();
+    let helper = frames.p1();
+    let globals: HashMap<Entity, GlobalTransform> = wanted
+        .into_iter()
+        .filter_map(|(entity, of)| Some((entity, helper.compute_global_transform(of?).ok()?)))
+        .collect();
+
+    for (entity, mut frame, mut transform, _) in frames.p0().iter_mut() {
+        let global = globals.get(&entity);
+        if frame.locked {
+            frame.update(window.size(), &camera_transform, camera.1);
+            // the center is in world space, under a parent it has to go through the parent's inverse first
+            let center = frame.center().extend(0.0);
+            let local = global.map_or(center, |parent| parent.affine().inverse().transform_point3(center));
+            transform.translation = local.truncate().extend(transform.translation.z);
+        } else if let Some(global) = global {
+            frame.place(global.translation().truncate(), window.size());
+        }
+    }
+}
+
+// runs after the camera has picked up the new viewport size, otherwise viewport_to_world_2d still
+// works with the old one. text reflows on its own since render_text lays it out every frame
+fn on_window_resize(
+    mut resize_events: EventReader<WindowResized>,
+    mut scale_factor_events: EventReader<WindowScaleFactorChanged>,
+    mut frames: Query<(&mut TextFrame, &GlobalTransform)>,
+    camera: Single<(&GlobalTransform, &Camera), With<Camera>>,
+    window: Single<&Window>,
+) {
+    let resized = resize_events.read().count() + scale_factor_events.read().count() != 0;
+    if !resized {
+        return;
+    }
+
+    for (mut frame, global_transform) in frames.iter_mut() {
+        if frame.locked {
+            frame.update(window.size(), camera.0, camera.1);
+        } else {
+            frame.place(global_transform.translation().truncate(), window.size());
+        }
+    }
+}
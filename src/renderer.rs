@@ -1,151 +1,495 @@
+use std::{borrow::Cow, sync::Arc};
+
 use bevy::{
-    color::palettes::css::{BLUE, GREEN, RED, WHITE}, 
-    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin}, 
+    color::palettes::css::{BLUE, FUCHSIA, GREEN, RED}, 
     prelude::* 
 };
+use font_parser::{
+    color::PaletteColor,
+    decoration::DecorationMetrics,
+    font_table_parser::{FontData, Glyph, FONT_SIZE_CONSTANT},
+    lint::GlyphLint,
+    geometry::Point
+};
+use unicode_segmentation::GraphemeCursor;
 
 use crate::{
     Debug, 
-    FontScaleANDLineHeight,
-    Frames,
-    GlyphData,
-    GlyphSpaces,
-    GlyphUnicode,
-    frame::Frame
+    font_asset::FontAsset,
+    frame::{BitmapSlot, FrameStyle, GlyphLabel, GlyphSlot, TextFrame}
 };
 
 const CURVE_RES: usize = 3;
+
+#[derive(Component)]
+pub struct BitmapGlyphSprite; // pooled, draw_bitmap_glyphs reuses them from frame to frame
+
+#[derive(Component)]
+pub struct GlyphLabelText; // pooled the same way by draw_glyph_labels
+
+// the parser has its own point type to stay bevy-free
+fn to_vec2(point: Point) -> Vec2 {
+    Vec2::new(point.x, point.y)
+}
+
+fn to_color(color: PaletteColor) -> Color {
+    Color::srgba_u8(color.r, color.g, color.b, color.a)
+}
+
 fn quadratic_curve(a: Vec2, b: Vec2, c: Vec2, alpha: f32) -> Vec2 {
     let p0 = a.lerp(b, alpha);
     let p1 = b.lerp(c, alpha);
     p0.lerp(p1, alpha)
 }
 
-fn draw_curve(a: Vec2, b: Vec2, c: Vec2, gizmos: &mut Gizmos) {
+fn draw_curve(a: Vec2, b: Vec2, c: Vec2, color: Color, gizmos: &mut Gizmos) {
     let mut previous_point = a;
     for i in 0..CURVE_RES {
         let alpha = (i+1) as f32/CURVE_RES as f32;
         let next_point = quadratic_curve(a, b, c, alpha);
-        gizmos.line_2d(previous_point, next_point, WHITE);
+        gizmos.line_2d(previous_point, next_point, color);
         previous_point = next_point;
     }
 }
 
+// gizmos only draw hairlines, so thick lines are a few of them stacked. gaps are x ranges from left.x to leave out
+fn draw_thick_line(left: Vec2, width: f32, thickness: f32, gaps: &[(f32, f32)], color: Color, gizmos: &mut Gizmos) {
+    let mut pieces = Vec::with_capacity(gaps.len() + 1);
+    let mut start = 0.0;
+    for (gap_start, gap_end) in gaps.iter() {
+        if *gap_start > start {
+            pieces.push((start, gap_start.min(width)));
+        }
+        start = start.max(*gap_end);
+    }
+    if start < width {
+        pieces.push((start, width));
+    }
+
+    let lines = thickness.ceil().max(1.0) as usize;
+    for line in 0..lines {
+        let y = left.y - thickness * line as f32 / lines as f32;
+        for (start, end) in pieces.iter() {
+            gizmos.line_2d(Vec2::new(left.x + start, y), Vec2::new(left.x + end, y), color);
+        }
+    }
+}
+
+// the frame's decorations under/through/over one glyph (or whitespace, with no glyph), metrics already scaled to the frame
+fn draw_decorations(style: &FrameStyle, metrics: &DecorationMetrics, ink: Option<&[(f32, f32)]>, pen: Vec2, advance: f32, frame_scale: f32, gizmos: &mut Gizmos) {
+    let (decorations, color) = (style.decorations, style.text_color);
+    if decorations.underline {
+        // ink skipping: leave out wherever the glyph dips into the underline, see FontAsset::underline_ink
+        let gaps: Vec<(f32, f32)> = ink.unwrap_or_default().iter().map(|(start, end)| (start * frame_scale, end * frame_scale)).collect();
+        draw_thick_line(pen + Vec2::new(0.0, metrics.underline_position), advance, metrics.underline_thickness, &gaps, color, gizmos);
+    }
+    if decorations.strikethrough {
+        draw_thick_line(pen + Vec2::new(0.0, metrics.strikeout_position), advance, metrics.strikeout_thickness, &[], color, gizmos);
+    }
+    if decorations.overline {
+        draw_thick_line(pen + Vec2::new(0.0, metrics.overline_position), advance, metrics.underline_thickness, &[], color, gizmos);
+    }
+}
+
+// one glyph with its origin (pen on the baseline) at origin. lints are only there in debug mode,
+// which also shows the contour points
+fn draw_glyph(gizmos: &mut Gizmos, glyph: &Glyph, origin: Vec2, frame: &TextFrame, color: Color, view: [f32; 4], lints: Option<&[GlyphLint]>) {
+    let [x_min, y_min, x_max, y_max] = view;
+    let bounding_box = &glyph.bounding_box; // (x_min, y_min, x_max, y_max)
+    let bb_x_min = bounding_box[0] * frame.frame_scale + origin.x;
+    let bb_y_min = bounding_box[1] * frame.frame_scale + origin.y;
+    let bb_x_max = bounding_box[2] * frame.frame_scale + origin.x;
+    let bb_y_max = bounding_box[3] * frame.frame_scale + origin.y;
+    if 
+        bb_x_min < x_min 
+        // || bb_x_min < frame.b_left.x 
+        || bb_y_min < y_min 
+        || bb_y_min < frame.b_left.y.min(frame.t_right.y) // any glyphs below the frame wont render (im letting the ones who MIGHT overflow (slightly) from the sides render)
+        ||bb_x_max > x_max 
+        // || bb_x_max > frame.t_right.x 
+        || bb_y_max > y_max 
+        // || bb_y_max > frame.t_right.y
+    {
+        return;
+    }
+
+    for contour_with_implied_points in glyph.contour_coordinates.iter() {
+        let mut i = 0;
+        let length = contour_with_implied_points.len();
+        while i < length {
+            let a = contour_with_implied_points[i];
+            let b = contour_with_implied_points[(i + 1) % length];
+            let c =contour_with_implied_points[(i + 2) % length];
+            let (p1,p2,p3) = 
+            (
+                to_vec2(a.0)*frame.frame_scale + origin, 
+                to_vec2(b.0)*frame.frame_scale + origin,
+                to_vec2(c.0)*frame.frame_scale + origin
+            );
+        
+            draw_curve(p1, p2, p3, color, gizmos);
+            if lints.is_some() {
+                gizmos.circle_2d(p1, 0.5, if a.1==0 { RED } else if a.1==1 { GREEN } else {BLUE});
+                gizmos.circle_2d(p2, 0.5, if b.1==0 { RED } else if b.1==1 { GREEN } else {BLUE});
+                gizmos.circle_2d(p3, 0.5, if c.1==0 { RED } else if c.1==1 { GREEN } else {BLUE});
+            }
+            
+            i += 2;
+        }
+    }
+
+    for lint in lints.unwrap_or_default() {
+        gizmos.circle_2d(to_vec2(lint.point)*frame.frame_scale + origin, 2.0, FUCHSIA);
+    }
+}
+
+// the outline to draw, grid fitted at ppem (pixels per em on screen) for frames with hinting on
+fn outline(font: &FontData, glyph_index: usize, ppem: Option<u16>) -> Arc<Glyph> {
+    match ppem {
+        Some(ppem) => font.hinted_glyph(glyph_index, ppem).map(|hinted| hinted.glyph.clone()).unwrap_or_default(),
+        None => font.glyph(glyph_index).unwrap_or_default(),
+    }
+}
+
+// scaled like the outlines, hinted advances are whole pixels so glyphs keep the same distance to the grid
+fn advance(font: &FontData, glyph_index: usize, ppem: Option<u16>) -> f32 {
+    match ppem.and_then(|ppem| font.hinted_glyph(glyph_index, ppem).ok()) {
+        Some(hinted) => hinted.advance,
+        None => font.glyph_spaces[glyph_index] * font.font_scale,
+    }
+}
+
+// a glyph out of the font, color fonts draw their SVG shapes or COLR layers bottom to top instead.
+// only plain outlines and COLR layers get hinted, SVG and COLRv1 art isn't meant to be grid fitted
+#[allow(clippy::too_many_arguments)]
+fn draw_font_glyph(gizmos: &mut Gizmos, font_asset: &FontAsset, glyph_index: usize, origin: Vec2, frame: &TextFrame, view: [f32; 4], debugging: bool, ppem: Option<u16>) {
+    let font = &font_asset.data;
+    // fonts with both usually have the same art twice, the SVG version is the richer one
+    if let Some(shapes) = font_asset.svg_shapes(glyph_index) {
+        for shape in shapes.iter() {
+            let color = shape.color.map_or(frame.style.text_color, to_color);
+            draw_glyph(gizmos, &shape.glyph, origin, frame, color, view, debugging.then_some(&[]));
+        }
+        return;
+    }
+
+    // palettes the font doesn't have fall back to its default one
+    let palette = font_asset.palettes.get(frame.palette).or(font_asset.palettes.first()).map_or(&[][..], Vec::as_slice);
+
+    // COLRv1 wins over the version 0 layers. gizmos can only draw outlines so every glyph in the paint graph
+    // gets one color, gradients are the average of their stops. the clip box isn't applied, gizmos can't clip
+    if let Some(color_glyph) = font_asset.color_paint(glyph_index) {
+        let foreground = frame.style.text_color.to_srgba().to_u8_array();
+        let foreground = PaletteColor { r: foreground[0], g: foreground[1], b: foreground[2], a: foreground[3] };
+        for painted in color_glyph.paint.flatten(palette, foreground) {
+            let glyph = painted.transform.transform_glyph(&font.glyph(painted.glyph_index).unwrap_or_default());
+            draw_glyph(gizmos, &glyph, origin, frame, to_color(painted.color), view, debugging.then_some(&[])); // lints wouldn't follow the transform
+        }
+        return;
+    }
+
+    let layers = font.color_layers(glyph_index).unwrap_or_default(); // a broken COLR just gets the plain outline
+    if layers.is_empty() {
+        let glyph = outline(font, glyph_index, ppem);
+        let lints = debugging.then(|| font_asset.lints(glyph_index));
+        draw_glyph(gizmos, &glyph, origin, frame, frame.style.text_color, view, lints.as_deref().map(Vec::as_slice));
+        return;
+    }
+
+    for layer in layers {
+        let color = layer.palette_index.and_then(|index| palette.get(index)).map_or(frame.style.text_color, |color| to_color(*color));
+        let glyph = outline(font, layer.glyph_index, ppem);
+        let lints = debugging.then(|| font_asset.lints(layer.glyph_index));
+        draw_glyph(gizmos, &glyph, origin, frame, color, view, lints.as_deref().map(Vec::as_slice));
+    }
+}
+
+// fonts with color bitmaps (or nothing but bitmaps) get a sprite instead of the outline, from the strike
+// that fits how big the text is on screen. glyphs without a bitmap fall back to their outline
+fn place_bitmap(font_asset: &FontAsset, glyph_index: usize, origin: Vec2, frame_scale: f32, pixels_per_unit: f32) -> Option<BitmapSlot> {
+    if !font_asset.data.prefers_bitmaps() {
+        return None;
+    }
+    let ppem = (FONT_SIZE_CONSTANT * frame_scale * pixels_per_unit).round().clamp(1.0, u16::MAX as f32) as u16;
+    let bitmap = font_asset.bitmap_placement(glyph_index, ppem)?;
+    let pixel = bitmap.pixel_size * frame_scale;
+    let size = Vec2::new(bitmap.width as f32, bitmap.height as f32) * pixel;
+    let center = origin + Vec2::new(bitmap.left, bitmap.bottom) * pixel + size * 0.5;
+    Some(BitmapSlot { glyph_index, ppem: bitmap.ppem, center, size })
+}
 
 pub fn render_text(
     mut gizmos: Gizmos,
     window: Single<&Window>, 
     camera: Single<(&GlobalTransform, &Camera), With<Camera>>,
-
-    diagnostics: Res<DiagnosticsStore>,
+    time: Res<Time>,
     
-    mut frames: ResMut<Frames>,
-    glyph_data: Res<GlyphData>,
-    glyph_unicodes: Res<GlyphUnicode>,
-    glyph_spaces: Res<GlyphSpaces>,
-    fontscale_and_lineheight: Res<FontScaleANDLineHeight>,
+    mut frames: Query<(&mut TextFrame, &InheritedVisibility)>,
+    fonts: Res<Assets<FontAsset>>,
     debugging: Res<Debug>,
 ) {
     let min = Vec2::new(-125.0,-125.0);
     let max = Vec2::new(window.width()*1.4, window.height()*1.4);
     let world_min = camera.1.viewport_to_world_2d(camera.0, min).unwrap();
     let world_max = camera.1.viewport_to_world_2d(camera.0, max).unwrap();
-    let (x_min, y_min, x_max, y_max) = (world_min.x, world_max.y, world_max.x, world_min.y); // weird as fuck i know
+    let view = [world_min.x, world_max.y, world_max.x, world_min.y]; // weird as fuck i know
+    let pixels_per_unit = (max.x - min.x) / (world_max.x - world_min.x); // for picking bitmap strikes and the hinting ppem
 
-    if frames.0.is_empty() {
-        super::setup_frames(camera, frames, window);
-        return;
-    }
-
-    for frame in frames.0.iter_mut() {
+    for (mut frame, visibility) in frames.iter_mut() {
+        if !visibility.get() {
+            continue;
+        }
+        let frame = &mut *frame; // reborrow so the text can be borrowed while the layout gets written
         frame.show(&mut gizmos);
+        let Some(font_asset) = fonts.get(&frame.font) else {
+            continue; // still loading
+        };
+        let font = &font_asset.data;
 
-        let font_scale = fontscale_and_lineheight.0 * frame.frame_scale;
-        let line_height = fontscale_and_lineheight.1 * frame.frame_scale;
+        let font_scale = font.font_scale * frame.frame_scale;
+        let ppem = frame.hinting.then(|| (FONT_SIZE_CONSTANT * frame.frame_scale * pixels_per_unit).round().clamp(1.0, u16::MAX as f32) as u16);
+        let line_height = font.line_height * frame.frame_scale;
+        let ascent = font.ascent * frame.frame_scale;
+        let decoration_metrics = {
+            let metrics = font.decoration_metrics();
+            let scaled = |value: f32| value * frame.frame_scale;
+            DecorationMetrics {
+                underline_position: scaled(metrics.underline_position),
+                underline_thickness: scaled(metrics.underline_thickness),
+                strikeout_position: scaled(metrics.strikeout_position),
+                strikeout_thickness: scaled(metrics.strikeout_thickness),
+                overline_position: scaled(metrics.overline_position),
+            }
+        };
 
         let frame_width = frame.t_right.distance(frame.t_left);
-        let mut padding = Vec2::new(0.0, line_height);
+        let mut padding = Vec2::new(0.0, -ascent); // first baseline sits the font's ascent below the top
         let text_anchor = frame.t_left; // change this along with the padding to anchor the text on some part of the frame
         
-        for word in frame.text.split_whitespace() {
-            let mut total_width_needed: f32 = 0.0;
-    
-            for char in word.chars().into_iter() {
-                let unicode = char as u32;
-                let glyph_index = glyph_unicodes.0[&unicode];
-                let glyph_advanced_width = &glyph_spaces.0[glyph_index];
-                total_width_needed += *glyph_advanced_width as f32 * font_scale;
-            }
+        // whatever the input method is composing gets drawn at the caret, its slots all point at the caret
+        // so the rest of the layout still lines up with frame.text
+        let (preedit_start, preedit_end) = (frame.caret, frame.caret + frame.preedit.len());
+        let text: Cow<str> = if frame.preedit.is_empty() {
+            Cow::Borrowed(&frame.text)
+        } else {
+            Cow::Owned(format!("{}{}{}", &frame.text[..preedit_start], frame.preedit, &frame.text[preedit_start..]))
+        };
+        let text_index = |i: usize| if i < preedit_start { i } else if i < preedit_end { preedit_start } else { i - frame.preedit.len() };
+
+        let mut layout = Vec::with_capacity(text.len() + 1);
+        let mut bitmaps = Vec::new();
+        let mut labels = Vec::new();
+        let label_size = line_height * 0.12;
+        let end_pen = if frame.vertical {
+            // columns go top to bottom and right to left, glyphs stay upright and centered on their column.
+            // pen is the top center of each glyph's em box and advance how far down the next one starts
+            let column_width = line_height;
+            let frame_height = frame.t_left.distance(frame.b_left);
+            let top = frame.t_left.y;
+            let mut pen = Vec2::new(frame.t_right.x - column_width * 0.5, top);
+            for (byte_index, char) in text.char_indices() {
+                if char == '\n' {
+                    layout.push(GlyphSlot { byte_index: text_index(byte_index), pen, advance: 0.0 });
+                    pen = Vec2::new(pen.x - column_width, top);
+                    continue;
+                }
 
-            if padding.x + total_width_needed > frame_width*0.95 {
-                padding.x = 0.0;
-                padding.y += line_height;
+                let glyph_index = font.vertical_glyph(font.unicodes_to_index.get(&(char as u32)).copied().unwrap_or(0));
+                let advance = font.vertical_advance(glyph_index) * font_scale;
+                let cluster_starts = GraphemeCursor::new(byte_index, text.len(), true).is_boundary(&text, 0).unwrap_or(true);
+                if pen.y < top && top - pen.y + advance > frame_height * 0.95 && cluster_starts { // never wrap in the middle of a grapheme cluster
+                    pen = Vec2::new(pen.x - column_width, top);
+                }
+
+                layout.push(GlyphSlot { byte_index: text_index(byte_index), pen, advance });
+                if (preedit_start..preedit_end).contains(&byte_index) {
+                    let side = pen + Vec2::new(column_width * 0.45, 0.0);
+                    gizmos.line_2d(side, side - Vec2::new(0.0, advance), frame.style.text_color);
+                }
+                // whitespace only moves the pen, same as in horizontal text. decorations are horizontal only for now
+                if !char.is_whitespace() {
+                    let glyph = font.glyph(glyph_index).unwrap_or_default();
+                    let origin = pen - Vec2::new(font.glyph_spaces[glyph_index] * font_scale * 0.5, font.vertical_origin(glyph_index, &glyph) * frame.frame_scale);
+                    match place_bitmap(font_asset, glyph_index, origin, frame.frame_scale, pixels_per_unit) {
+                        Some(bitmap) => bitmaps.push(bitmap),
+                        None => draw_font_glyph(&mut gizmos, font_asset, glyph_index, origin, frame, view, debugging.0, ppem),
+                    }
+                    if debugging.0 {
+                        labels.push(GlyphLabel { glyph_index, center: pen - Vec2::new(0.0, advance - label_size), size: label_size });
+                    }
+                }
+                pen.y -= advance;
             }
+            pen
+        } else {
+            let mut word_start = 0;
+            for piece in text.split_inclusive(char::is_whitespace) {
+                // every piece is a word followed by at most one whitespace char
+                let word = piece.trim_end_matches(char::is_whitespace);
+                let whitespace = piece[word.len()..].chars().next();
+                let mut total_width_needed: f32 = 0.0;
+    
+                for char in word.chars() {
+                    let unicode = char as u32;
+                    let glyph_index = font.unicodes_to_index.get(&unicode).copied().unwrap_or(0); // glyph 0 is .notdef (the missing glyph box)
+                    total_width_needed += advance(font, glyph_index, ppem) * frame.frame_scale;
+                }
+
+                if padding.x + total_width_needed > frame_width*0.95 {
+                    padding.x = 0.0;
+                    padding.y -= line_height;
+                }
 
-            for char in word.chars().into_iter() {
-                let unicode = char as u32;
-                let glyph_index = glyph_unicodes.0[&unicode];
-                let contour_coordinates = &glyph_data.0[glyph_index].contour_coordinates;
-                let glyph_advanced_width = &glyph_spaces.0[glyph_index];
-                let bounding_box = &glyph_data.0[glyph_index].bounding_box; // (x_min, y_min, x_max, y_max)
+                for (char_offset, char) in word.char_indices() {
+                    let unicode = char as u32;
+                    let glyph_index = font.unicodes_to_index.get(&unicode).copied().unwrap_or(0); // glyph 0 is .notdef (the missing glyph box)
+                    let glyph = outline(font, glyph_index, ppem); // a glyph that fails to parse just doesnt get drawn
+                    let glyph_advanced_width = advance(font, glyph_index, ppem) * frame.frame_scale;
                
-               let bb_x_min = bounding_box[0] * frame.frame_scale + padding.x + text_anchor.x;
-               let bb_y_min = bounding_box[1] * frame.frame_scale + padding.y + text_anchor.y;
-               let bb_x_max = bounding_box[2] * frame.frame_scale + padding.x + text_anchor.x;
-               let bb_y_max = bounding_box[3] * frame.frame_scale + padding.y + text_anchor.y;
-                for contour_with_implied_points in contour_coordinates {
-                    if 
-                        bb_x_min < x_min 
-                        // || bb_x_min < frame.b_left.x 
-                        || bb_y_min < y_min 
-                        || bb_y_min < frame.b_left.y.min(frame.t_right.y) // any glyphs below the frame wont render (im letting the ones who MIGHT overflow (slightly) from the sides render)
-                        ||bb_x_max > x_max 
-                        // || bb_x_max > frame.t_right.x 
-                        || bb_y_max > y_max 
-                        // || bb_y_max > frame.t_right.y
-                    {
-                        break;
+                    let byte_index = word_start + char_offset;
+                    layout.push(GlyphSlot { byte_index: text_index(byte_index), pen: padding + text_anchor, advance: glyph_advanced_width });
+                    if (preedit_start..preedit_end).contains(&byte_index) {
+                        let underline = padding + text_anchor - Vec2::new(0.0, line_height * 0.15);
+                        gizmos.line_2d(underline, underline + Vec2::new(glyph_advanced_width, 0.0), frame.style.text_color);
+                    }
+
+                    match place_bitmap(font_asset, glyph_index, padding + text_anchor, frame.frame_scale, pixels_per_unit) {
+                        Some(bitmap) => bitmaps.push(bitmap),
+                        None => draw_font_glyph(&mut gizmos, font_asset, glyph_index, padding + text_anchor, frame, view, debugging.0, ppem),
+                    }
+                    if debugging.0 {
+                        // in the descender room, two lines so long names don't run into the next glyph's as much
+                        let center = padding + text_anchor + Vec2::new(glyph_advanced_width * 0.5, font.descent * frame.frame_scale);
+                        labels.push(GlyphLabel { glyph_index, center, size: label_size });
                     }
 
-                    let mut i = 0;
-                    let length = contour_with_implied_points.len();
-                    while i < length {
-                        let a = contour_with_implied_points[i];
-                        let b = contour_with_implied_points[(i + 1) % length];
-                        let c =contour_with_implied_points[(i + 2) % length];
-                        let (p1,p2,p3) = 
-                        (
-                            a.0*frame.frame_scale + padding + text_anchor, 
-                            b.0*frame.frame_scale + padding + text_anchor,
-                            c.0*frame.frame_scale + padding + text_anchor
-                        );
-                    
-                        draw_curve(p1, p2, p3, &mut gizmos);
-                        if debugging.0 {
-                            gizmos.circle_2d(p1, 0.5, if a.1==0 { RED } else if a.1==1 { GREEN } else {BLUE});
-                            gizmos.circle_2d(p2, 0.5, if b.1==0 { RED } else if b.1==1 { GREEN } else {BLUE});
-                            gizmos.circle_2d(p3, 0.5, if c.1==0 { RED } else if c.1==1 { GREEN } else {BLUE});
-                        }
-                        
-                        i += 2;
+                    let ink = frame.style.decorations.underline.then(|| font_asset.underline_ink(glyph_index, ppem, &glyph));
+                    draw_decorations(&frame.style, &decoration_metrics, ink.as_deref().map(Vec::as_slice), padding + text_anchor, glyph_advanced_width, frame.frame_scale, &mut gizmos);
+                    padding.x += glyph_advanced_width;
+                    let cluster_ends = GraphemeCursor::new(char_offset + char.len_utf8(), word.len(), true).is_boundary(word, 0).unwrap_or(true);
+                    if padding.x > frame_width*0.95 && cluster_ends { // never wrap in the middle of a grapheme cluster
+                        padding.x = 0.0;
+                        padding.y -= line_height;
+                    }
+                }
+
+                if let Some(whitespace) = whitespace {
+                    let whitespace_width = 30.0 * frame.frame_scale;
+                    layout.push(GlyphSlot { byte_index: text_index(word_start + word.len()), pen: padding + text_anchor, advance: whitespace_width });
+                    if whitespace == '\n' {
+                        padding.x = 0.0;
+                        padding.y -= line_height;
+                    } else {
+                        draw_decorations(&frame.style, &decoration_metrics, None, padding + text_anchor, whitespace_width, frame.frame_scale, &mut gizmos);
+                        padding.x += whitespace_width;
                     }
+                }
+                word_start += piece.len();
             }
 
-                padding.x += glyph_advanced_width * font_scale;
-                if padding.x > frame_width*0.95 {
-                    padding.x = 0.0;
-                    padding.y += line_height;
+            padding + text_anchor
+        };
+
+        // end of text, so the caret has somewhere to sit after the last char
+        layout.push(GlyphSlot { byte_index: text_index(text.len()), pen: end_pen, advance: 0.0 });
+        frame.layout = layout;
+        frame.bitmaps = bitmaps;
+        frame.labels = labels;
+        frame.line_height = line_height;
+        frame.ascent = ascent;
+        frame.descent = font.descent * frame.frame_scale;
+        if frame.editing {
+            frame.show_caret(&mut gizmos, time.elapsed_secs());
+        }
+    }
+}
+
+// gizmos can't draw images so bitmap glyphs are sprites. one entity per bitmap on screen, reused every frame
+// and hidden while there are more of them than bitmaps
+pub fn draw_bitmap_glyphs(
+    mut commands: Commands,
+    frames: Query<(&TextFrame, &InheritedVisibility)>,
+    fonts: Res<Assets<FontAsset>>,
+    mut images: ResMut<Assets<Image>>,
+    mut sprites: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<BitmapGlyphSprite>>,
+) {
+    let mut pool = sprites.iter_mut();
+    for (frame, visibility) in frames.iter() {
+        if !visibility.get() {
+            continue;
+        }
+        let Some(font_asset) = fonts.get(&frame.font) else {
+            continue;
+        };
+
+        for slot in &frame.bitmaps {
+            let Some(texture) = font_asset.bitmap_texture(slot.glyph_index, slot.ppem, &mut images) else {
+                continue;
+            };
+            let color = if texture.colored { Color::WHITE } else { frame.style.text_color };
+            let sprite = Sprite { image: texture.image, color, custom_size: Some(slot.size), ..default() };
+            let transform = Transform::from_translation(slot.center.extend(0.0));
+            match pool.next() {
+                Some((mut pooled_sprite, mut pooled_transform, mut pooled_visibility)) => {
+                    *pooled_sprite = sprite;
+                    *pooled_transform = transform;
+                    *pooled_visibility = Visibility::Inherited;
+                }
+                None => {
+                    commands.spawn((sprite, transform, BitmapGlyphSprite));
                 }
             }
-            padding.x += 30.0 * frame.frame_scale; // whitespace for each word
         }
     }
 
-    if let Some(fps) = diagnostics
-        .get(&FrameTimeDiagnosticsPlugin::FPS)
-        .and_then(|fps| fps.smoothed())
-    {
-        let fps_frame = frames.0.get_frame_by_name(String::from("fps")).ok_or_else(|| format!("FPS Frame not found!")).unwrap();
-        fps_frame.text = format!("FPS: {:.0}", fps);
+    for (_, _, mut visibility) in pool {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+// debug mode only. gizmos can't draw text either, so each glyph's index and post name is a Text2d under it
+pub fn draw_glyph_labels(
+    mut commands: Commands,
+    frames: Query<(&TextFrame, &InheritedVisibility)>,
+    fonts: Res<Assets<FontAsset>>,
+    mut texts: Query<(&mut Text2d, &mut TextFont, &mut TextColor, &mut Transform, &mut Visibility), With<GlyphLabelText>>,
+) {
+    let mut pool = texts.iter_mut();
+    for (frame, visibility) in frames.iter() {
+        if !visibility.get() {
+            continue;
+        }
+        let Some(font_asset) = fonts.get(&frame.font) else {
+            continue;
+        };
+
+        let color = TextColor(frame.style.text_color.with_alpha(0.6));
+        for label in &frame.labels {
+            let text = format!("{}\n{}", label.glyph_index, font_asset.data.glyph_name(label.glyph_index).unwrap_or("-"));
+            let transform = Transform::from_translation(label.center.extend(1.0));
+            match pool.next() {
+                Some((mut pooled_text, mut pooled_font, mut pooled_color, mut pooled_transform, mut pooled_visibility)) => {
+                    // only touch what changed, new text or sizes get laid out again
+                    if pooled_text.0 != text {
+                        pooled_text.0 = text;
+                    }
+                    if pooled_font.font_size != label.size {
+                        pooled_font.font_size = label.size;
+                    }
+                    if pooled_color.0 != color.0 {
+                        *pooled_color = color;
+                    }
+                    pooled_transform.set_if_neq(transform);
+                    pooled_visibility.set_if_neq(Visibility::Inherited);
+                }
+                None => {
+                    commands.spawn((Text2d::new(text), TextFont { font_size: label.size, ..default() }, color, transform, GlyphLabelText));
+                }
+            }
+        }
     }
-}
\ No newline at end of file
+
+    for (_, _, _, _, mut visibility) in pool {
+        visibility.set_if_neq(Visibility::Hidden);
+    }
+}
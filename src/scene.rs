@@ -0,0 +1,190 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    font_asset::load_font,
+    frame::{Decorations, FrameStyle, TextFrame}
+};
+
+// a frames file, see assets/frames.ron
+#[derive(Serialize, Deserialize)]
+pub struct FrameScene {
+    pub font: String, // used by frames that dont name their own
+    #[serde(default)]
+    pub face_index: u32, // of the default font, if it is a collection
+    pub frames: Vec<FrameDescription>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FrameDescription {
+    pub name: String,
+    pub text: String,
+    pub size: (f32, f32), // fraction of the window
+    pub position: (f32, f32), // locked frames: fraction of the window, world frames: world coordinates
+    pub locked: bool,
+    #[serde(default)]
+    pub scale: Option<f32>,
+    #[serde(default)]
+    pub font: Option<String>,
+    #[serde(default = "editable_default")]
+    pub editable: bool,
+    #[serde(default)]
+    pub style: StyleDescription,
+    #[serde(default)]
+    pub vertical: bool,
+    #[serde(default)]
+    pub palette: usize,
+    #[serde(default)]
+    pub hinting: bool,
+}
+
+fn editable_default() -> bool {
+    true
+}
+
+// colors are srgba
+#[derive(Serialize, Deserialize)]
+pub struct StyleDescription {
+    pub text_color: [f32; 4],
+    pub border_color: Option<[f32; 4]>,
+    #[serde(default)]
+    pub underline: bool,
+    #[serde(default)]
+    pub strikethrough: bool,
+    #[serde(default)]
+    pub overline: bool,
+}
+
+impl Default for StyleDescription {
+    fn default() -> Self {
+        FrameStyle::default().into()
+    }
+}
+
+impl From<FrameStyle> for StyleDescription {
+    fn from(style: FrameStyle) -> Self {
+        Self {
+            text_color: style.text_color.to_srgba().to_f32_array(),
+            border_color: style.border_color.map(|color| color.to_srgba().to_f32_array()),
+            underline: style.decorations.underline,
+            strikethrough: style.decorations.strikethrough,
+            overline: style.decorations.overline,
+        }
+    }
+}
+
+impl From<&StyleDescription> for FrameStyle {
+    fn from(style: &StyleDescription) -> Self {
+        Self {
+            text_color: Srgba::from_f32_array(style.text_color).into(),
+            border_color: style.border_color.map(|color| Srgba::from_f32_array(color).into()),
+            decorations: Decorations { underline: style.underline, strikethrough: style.strikethrough, overline: style.overline },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Write(ron::Error),
+}
+
+impl std::fmt::Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneError::Io(err) => write!(f, "could not access frames file: {err}"),
+            SceneError::Parse(err) => write!(f, "could not parse frames file: {err}"),
+            SceneError::Write(err) => write!(f, "could not write frames file: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<std::io::Error> for SceneError {
+    fn from(err: std::io::Error) -> Self {
+        SceneError::Io(err)
+    }
+}
+
+impl FrameScene {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        let contents = fs::read_to_string(path)?;
+        ron::from_str(&contents).map_err(SceneError::Parse)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SceneError> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(SceneError::Write)?;
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    pub fn spawn(&self, commands: &mut Commands, asset_server: &AssetServer) {
+        let default_font = load_font(asset_server, self.font.clone(), self.face_index);
+        for description in self.frames.iter() {
+            let font = description.font.as_ref().map_or_else(|| default_font.clone(), |path| asset_server.load(path));
+            let position = Vec2::new(description.position.0, description.position.1);
+            let mut frame = TextFrame::new(
+                description.name.clone(),
+                description.text.clone(),
+                Vec2::new(description.size.0, description.size.1),
+                if description.locked { position } else { Vec2::ZERO },
+                description.locked,
+                description.scale,
+            )
+            .with_font(font)
+            .with_style((&description.style).into())
+            .with_palette(description.palette);
+            if !description.editable {
+                frame = frame.read_only();
+            }
+            if description.vertical {
+                frame = frame.vertical();
+            }
+            if description.hinting {
+                frame = frame.hinted();
+            }
+
+            if description.locked {
+                commands.spawn(frame);
+            } else {
+                commands.spawn((frame, Transform::from_translation(position.extend(0.0))));
+            }
+        }
+    }
+
+    // snapshot of the frames as they are right now, edited text included
+    pub fn from_frames<'a>(
+        frames: impl IntoIterator<Item = (&'a TextFrame, &'a Transform)>,
+        asset_server: &AssetServer,
+        default_font: String,
+        face_index: u32,
+    ) -> Self {
+        let frames = frames
+            .into_iter()
+            .map(|(frame, transform)| {
+                let position = if frame.locked { frame.position() } else { transform.translation.truncate() };
+                let font = asset_server.get_path(frame.font.id()).map(|path| path.to_string()).filter(|path| *path != default_font);
+                FrameDescription {
+                    name: frame.name.clone(),
+                    text: frame.text.clone(),
+                    size: (frame.size().x, frame.size().y),
+                    position: (position.x, position.y),
+                    locked: frame.locked,
+                    scale: Some(frame.frame_scale),
+                    font,
+                    editable: frame.editable,
+                    style: frame.style.into(),
+                    vertical: frame.vertical,
+                    palette: frame.palette,
+                    hinting: frame.hinting,
+                }
+            })
+            .collect();
+
+        Self { font: default_font, face_index, frames }
+    }
+}